@@ -0,0 +1,44 @@
+//! Solving large black and white puzzles : the line solvers on the board, one proposition
+//! at a time or a whole sweep at a time (in parallel with the `parallel` feature), against
+//! the packed board of `deduce`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use picross_rs::generate::random_image;
+use picross_rs::{deduce, Game, SolverBuilder};
+
+fn large_puzzles(c: &mut Criterion) {
+    let mut group = c.benchmark_group("large_puzzles");
+    group.sample_size(10);
+    for size in [100, 200] {
+        let game = Game::with_image(random_image(size, size, 0.6, 1));
+
+        group.bench_with_input(BenchmarkId::new("solver", size), &game.image, |b, image| {
+            let solver = SolverBuilder::new().build();
+            b.iter(|| {
+                let mut game = Game::with_image(image.clone());
+                while let Ok(Some(proposition)) = solver.solve(&game) {
+                    proposition.merge(&mut game.board);
+                }
+                game.board
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("sweep", size), &game.image, |b, image| {
+            let solver = SolverBuilder::new().build();
+            b.iter(|| {
+                let mut game = Game::with_image(image.clone());
+                solver.run(&mut game, |_, _, _| {}).unwrap();
+                game.board
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("deduce", size), &game, |b, game| {
+            b.iter(|| deduce(game).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, large_puzzles);
+criterion_main!(benches);
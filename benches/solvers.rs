@@ -0,0 +1,87 @@
+//! The clue extraction from pictures, each line algorithm on single lines, and full solves
+//! of generated puzzles.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use image::Rgb;
+
+use picross_rs::generate::random_image;
+use picross_rs::{
+    Clue, ColorPlacements, FullLine, Game, GameLine, GameView, Image, Pixel, Placements,
+    SolverAlgo, SolverBuilder, BLANK,
+};
+
+const LENGTHS: [u32; 4] = [10, 25, 100, 500];
+
+fn clue_extraction(c: &mut Criterion) {
+    let mut group = c.benchmark_group("clue_extraction");
+    for file in ["test/4x4-c.png", "test/8x5-fish.png"] {
+        group.bench_with_input(BenchmarkId::new("from_image", file), file, |b, file| {
+            b.iter(|| Image::from_image(file).unwrap())
+        });
+    }
+    for size in [100, 500] {
+        let img = random_image(size, size, 0.5, 1).to_rgb_image().unwrap();
+        group.bench_with_input(BenchmarkId::new("from_rgb_image", size), &img, |b, img| {
+            b.iter(|| Image::from_rgb_image(img.clone()))
+        });
+    }
+    group.finish();
+}
+
+/// The clues of the first row of a random picture, with a single color or with two
+fn line_clues(len: u32, colors: bool) -> Image {
+    let mut img = random_image(len, 1, 0.6, u64::from(len)).to_rgb_image().unwrap();
+    if colors {
+        for (x, _, pixel) in img.enumerate_pixels_mut() {
+            if *pixel != Rgb([0xFF, 0xFF, 0xFF]) && x % 3 == 0 {
+                *pixel = Rgb([0xFF, 0, 0]);
+            }
+        }
+    }
+    Image::from_rgb_image(img)
+}
+
+fn line_algorithms(c: &mut Criterion) {
+    let algos: [(Box<dyn SolverAlgo>, bool); 3] = [
+        (Box::new(FullLine), false),
+        (Box::new(Placements), false),
+        (Box::new(ColorPlacements), true),
+    ];
+    let mut group = c.benchmark_group("line_algorithms");
+    for (algo, colors) in &algos {
+        for len in LENGTHS {
+            let image = line_clues(len, *colors);
+            let clues: &[Clue] = &image.rows[0];
+            let line = vec![Pixel::Color(BLANK); len as usize];
+            group.bench_with_input(BenchmarkId::new(algo.name(), len), &line, |b, line| {
+                b.iter(|| {
+                    let game_line = GameLine::new(GameView::Row, 0, line.iter().collect(), clues);
+                    algo.get_proposition(&game_line)
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+fn full_solves(c: &mut Criterion) {
+    let solver = SolverBuilder::new().build();
+    let mut group = c.benchmark_group("full_solves");
+    group.sample_size(10);
+    for size in [5, 10, 25, 50, 100] {
+        let image = random_image(size, size, 0.6, 1);
+        group.bench_with_input(BenchmarkId::from_parameter(size), &image, |b, image| {
+            b.iter(|| {
+                let mut game = Game::with_image(image.clone());
+                while let Ok(Some(proposition)) = solver.solve(&game) {
+                    proposition.merge(&mut game.board);
+                }
+                game.board
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, clue_extraction, line_algorithms, full_solves);
+criterion_main!(benches);
@@ -0,0 +1,24 @@
+//! Parse JSON puzzles and boards, and write them back
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use picross_rs::{json, Board};
+use picross_rs_fuzz::check_image;
+
+fuzz_target!(|data: &str| {
+    if let Ok(image) = json::parse_image(data) {
+        check_image(&image);
+        let read = json::parse_image(&json::image_to_json(&image).unwrap()).unwrap();
+        assert_eq!(read.rows, image.rows);
+        assert_eq!(read.cols, image.cols);
+        assert_eq!(read.palette, image.palette);
+        assert_eq!(read.to_rgb_image(), image.to_rgb_image());
+    }
+
+    if let Ok(board) = serde_json::from_str::<Board>(data) {
+        let written = serde_json::to_string(&board).unwrap();
+        let read: Board = serde_json::from_str(&written).unwrap();
+        assert_eq!(serde_json::to_string(&read).unwrap(), written);
+    }
+});
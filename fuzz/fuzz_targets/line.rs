@@ -0,0 +1,93 @@
+//! Run every line algorithm on arbitrary clues and cells
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use picross_rs::{
+    blocks, Clue, ColorPlacements, FullLine, GameLine, GameView, Pixel, Placements, Reason,
+    SolverAlgo,
+};
+use picross_rs_fuzz::possible_cells;
+
+/// The colors of the clues and of the cells are taken among a few ones, so that blocks
+/// of the same color often follow each other
+const COLORS: u8 = 3;
+
+/// The longest line whose placements are all tried, to check the proposed cells
+const MAX_BRUTE_FORCE: usize = 16;
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    column: bool,
+    index: u8,
+    /// (color, count - 1) of each clue
+    clues: Vec<(u8, u8)>,
+    /// A cross, or the color of the cell, 0 being unknown
+    cells: Vec<Option<u8>>,
+}
+
+fuzz_target!(|input: Input| {
+    let view = if input.column { GameView::Column } else { GameView::Row };
+    let index = input.index as usize;
+    let clues: Vec<Clue> = input
+        .clues
+        .iter()
+        .map(|&(color, count)| Clue { color: 1 + color % COLORS, count: 1 + count as u32 })
+        .collect();
+    let cells: Vec<Pixel> = input
+        .cells
+        .iter()
+        .map(|cell| cell.map_or(Pixel::Cross, |color| Pixel::Color(color % (COLORS + 1))))
+        .collect();
+    let unknown = Pixel::Color(0);
+    let solved = !cells.contains(&unknown) && blocks(&cells) == clues;
+    // For a short line, the values of each cell in the placements which fit, or `None`
+    // if there isn't any
+    let possible = (cells.len() <= MAX_BRUTE_FORCE).then(|| possible_cells(&cells, &clues));
+
+    let algos: [Box<dyn SolverAlgo>; 3] =
+        [Box::new(FullLine), Box::new(Placements), Box::new(ColorPlacements)];
+    for algo in &algos {
+        let line = GameLine::new(view, index, cells.iter().collect(), &clues);
+        match algo.get_proposition(&line) {
+            Ok(Some(proposition)) => {
+                assert!(!solved, "{} proposes cells on a solved line", algo.name());
+                assert_eq!(proposition.view(), view);
+                assert_eq!(proposition.index(), index);
+                let mut changes = false;
+                for (x, y, pixel) in proposition.cells() {
+                    let (i, line_index) = match view {
+                        GameView::Row => (x, y),
+                        GameView::Column => (y, x),
+                    };
+                    assert_eq!(line_index, index);
+                    assert!(i < cells.len(), "{} proposes the cell {} out of the line", algo.name(), i);
+                    assert!(pixel != unknown, "{} proposes an unknown cell", algo.name());
+                    match cells[i] {
+                        cell if cell == unknown => changes = true,
+                        cell => assert_eq!(cell, pixel, "{} changes the cell {}", algo.name(), i),
+                    }
+                    // The cell has this value in every placement
+                    if let Some(Some(possible)) = &possible {
+                        assert_eq!(possible[i], [pixel], "{} deduces a wrong cell {}", algo.name(), i);
+                    }
+                }
+                assert!(changes, "{} proposes no new cell", algo.name());
+            }
+            Ok(None) => {}
+            Err(contradiction) => {
+                assert!(!solved, "{} rejects a solved line", algo.name());
+                assert!(
+                    !matches!(possible, Some(Some(_))),
+                    "{} rejects a line with a placement",
+                    algo.name()
+                );
+                assert_eq!((contradiction.view, contradiction.index), (view, index));
+                if let Reason::WrongCell(i) = contradiction.reason {
+                    assert!(i < cells.len());
+                }
+            }
+        }
+    }
+});
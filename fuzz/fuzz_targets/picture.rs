@@ -0,0 +1,37 @@
+//! Compute the clues of arbitrary pictures. The pictures are built from the input
+//! rather than decoded, the decoders being fuzzed by the `image` crate.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use image::{DynamicImage, Rgb, RgbImage};
+use libfuzzer_sys::fuzz_target;
+use picross_rs::{Image, WHITE};
+use picross_rs_fuzz::check_image;
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    width: u8,
+    /// The color of each cell, row by row, `None` being white
+    cells: Vec<Option<[u8; 3]>>,
+}
+
+fuzz_target!(|input: Input| {
+    let width = u32::from(input.width.max(1));
+    let height = input.cells.len() as u32 / width;
+    let img = RgbImage::from_fn(width, height, |x, y| {
+        input.cells[(x + y * width) as usize].map_or(WHITE, Rgb)
+    });
+    match Image::from_dynamic_image(DynamicImage::ImageRgb8(img.clone())) {
+        Ok(image) => {
+            check_image(&image);
+            assert_eq!(image.to_rgb_image().as_ref(), Some(&img));
+        }
+        Err(_) => {
+            let mut colors: Vec<[u8; 3]> = img.pixels().map(|pixel| pixel.0).collect();
+            colors.sort();
+            colors.dedup();
+            assert!(colors.len() > 256, "a picture with {} colors is rejected", colors.len());
+        }
+    }
+});
@@ -0,0 +1,26 @@
+//! Parse text grids as a solution and as a board, and write them back
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use picross_rs::text;
+use picross_rs_fuzz::check_image;
+
+fuzz_target!(|data: &str| {
+    let Ok(image) = text::parse_image(data) else {
+        return;
+    };
+    check_image(&image);
+    let written = text::image_to_text(&image.to_rgb_image().unwrap()).unwrap();
+    let read = text::parse_image(&written).unwrap();
+    assert_eq!(read.rows, image.rows);
+    assert_eq!(read.cols, image.cols);
+
+    // The solution is also a board, the empty cells being crossed
+    let board = text::parse_board(data, &image.palette).unwrap();
+    assert_eq!((board.width(), board.height()), (image.width as usize, image.height as usize));
+    assert_eq!(board.count_unknown(), 0);
+    let written = text::board_to_text(&board, &image.palette).unwrap();
+    let read = text::parse_board(&written, &image.palette).unwrap();
+    assert_eq!(text::board_to_text(&read, &image.palette).unwrap(), written);
+});
@@ -0,0 +1,73 @@
+//! The checks shared by the fuzz targets : what a parsed puzzle or a proposition must
+//! look like, whatever the input was.
+
+use picross_rs::{Board, Clue, Image, Pixel, BLANK};
+
+/// The puzzle has a clue list for each line, with colors of its palette, and its
+/// solution, if known, gives back the clues
+pub fn check_image(image: &Image) {
+    assert_eq!(image.rows.len(), image.height as usize);
+    assert_eq!(image.cols.len(), image.width as usize);
+    for clue in image.rows.iter().chain(&image.cols).flatten() {
+        assert!(clue.count > 0, "empty clue");
+        assert!(clue.color != BLANK && (clue.color as usize) < image.palette.len());
+    }
+    if let Some(img) = image.to_rgb_image() {
+        assert_eq!(img.dimensions(), (image.width, image.height));
+        assert!(Board::from(image).eq(image));
+    }
+}
+
+/// The values each cell of the line takes in the placements of the clues that fit its
+/// known cells, found by trying them all. `None` if no placement fits.
+///
+/// Only for short lines : the number of placements grows fast with the length.
+pub fn possible_cells(cells: &[Pixel], clues: &[Clue]) -> Option<Vec<Vec<Pixel>>> {
+    let mut possible = vec![vec![]; cells.len()];
+    let mut line = vec![Pixel::Cross; cells.len()];
+    let mut found = false;
+    place(cells, clues, 0, 0, &mut line, &mut possible, &mut found);
+    found.then_some(possible)
+}
+
+/// Place the clues `j..` from the cell `start`, the cells before it being placed in `line`
+fn place(
+    cells: &[Pixel],
+    clues: &[Clue],
+    j: usize,
+    start: usize,
+    line: &mut [Pixel],
+    possible: &mut [Vec<Pixel>],
+    found: &mut bool,
+) {
+    let fits = |line: &[Pixel], range: std::ops::Range<usize>| {
+        range.into_iter().all(|i| cells[i] == Pixel::Color(BLANK) || cells[i] == line[i])
+    };
+    let n = cells.len();
+    let clue = match clues.get(j) {
+        Some(clue) => clue,
+        None => {
+            line[start..].fill(Pixel::Cross);
+            if fits(line, start..n) {
+                *found = true;
+                for (values, &pixel) in possible.iter_mut().zip(line.iter()) {
+                    if !values.contains(&pixel) {
+                        values.push(pixel);
+                    }
+                }
+            }
+            return;
+        }
+    };
+    // Blocks of the same color need a gap between them
+    let gap = usize::from(j > 0 && clues[j - 1].color == clue.color);
+    let count = clue.count as usize;
+    for begin in start + gap..(n + 1).saturating_sub(count) {
+        let end = begin + count;
+        line[start..begin].fill(Pixel::Cross);
+        line[begin..end].fill(Pixel::Color(clue.color));
+        if fits(line, start..end) {
+            place(cells, clues, j + 1, end, line, possible, found);
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use std::fs::File;
+use std::path::Path;
+use std::time::Duration;
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, DynamicImage, Frame, RgbImage};
+
+use crate::board::Board;
+use crate::picross_image::{Image, ImageError};
+use crate::render;
+
+/// Record the boards of a game as the frames of an animation
+pub struct Recorder<'a> {
+    image: &'a Image,
+    previous: Option<Board>,
+    frames: Vec<RgbImage>,
+}
+
+impl<'a> Recorder<'a> {
+    pub fn new(image: &'a Image) -> Self {
+        Recorder {
+            image,
+            previous: None,
+            frames: vec![],
+        }
+    }
+
+    /// Add a frame, highlighting the cells changed since the previous one
+    pub fn record(&mut self, board: &Board) {
+        let mut frame = render::to_png(self.image, board);
+        if let Some(previous) = &self.previous {
+            let mut changed = vec![];
+            for y in 0..board.height() {
+                for x in 0..board.width() {
+                    if board.get_pixel(x, y) != previous.get_pixel(x, y) {
+                        changed.push((x, y));
+                    }
+                }
+            }
+            render::highlight(&mut frame, self.image, &changed);
+        }
+        self.frames.push(frame);
+        self.previous = Some(board.clone());
+    }
+
+    pub fn frames(&self) -> &[RgbImage] {
+        &self.frames
+    }
+
+    /// Write the frames as an animated GIF, looping forever
+    pub fn save_gif<P>(self, filename: P, delay: Duration) -> Result<(), ImageError>
+    where
+        P: AsRef<Path>,
+    {
+        let mut encoder = GifEncoder::new(File::create(filename)?);
+        encoder.set_repeat(Repeat::Infinite)?;
+        let delay = Delay::from_saturating_duration(delay);
+        encoder.encode_frames(self.frames.into_iter().map(|frame| {
+            Frame::from_parts(DynamicImage::ImageRgb8(frame).to_rgba8(), 0, 0, delay)
+        }))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgb;
+
+    use crate::game::Game;
+    use crate::solver::SolverBuilder;
+
+    use super::*;
+
+    #[test]
+    fn it_records_the_solving_steps() {
+        let mut game = Game::new("test/4x4-c.png").unwrap();
+        let solver = SolverBuilder::new().build();
+        let mut recorder = Recorder::new(&game.image);
+        recorder.record(&game.board);
+        let mut steps = 0;
+        while let Some((_, proposition)) = solver.step(&game).unwrap() {
+            proposition.merge(&mut game.board);
+            recorder.record(&game.board);
+            steps += 1;
+        }
+        assert_eq!(recorder.frames().len(), steps + 1);
+
+        // The first row is solved first, and highlighted
+        let first = &recorder.frames()[0];
+        let second = &recorder.frames()[1];
+        let highlight = Rgb([0xFF, 0x8C, 0x00]);
+        assert!(!first.pixels().any(|p| *p == highlight));
+        assert!(second.pixels().any(|p| *p == highlight));
+
+        let filename = std::env::temp_dir().join("picross-rs-4x4-c.gif");
+        recorder.save_gif(&filename, Duration::from_millis(500)).unwrap();
+        assert!(image::open(&filename).is_ok());
+    }
+}
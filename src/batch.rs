@@ -0,0 +1,258 @@
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::game::Game;
+use crate::solver::{Solver, SolverBuilder};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    Solved,
+    Stuck,
+    /// The board can't satisfy the clues
+    Contradiction,
+    /// The puzzle couldn't be loaded, with the error message
+    Error(String),
+}
+
+impl Status {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Status::Solved => "solved",
+            Status::Stuck => "stuck",
+            Status::Contradiction => "contradiction",
+            Status::Error(_) => "error",
+        }
+    }
+
+    /// The error message, for a puzzle that couldn't be loaded
+    fn error(&self) -> Option<&str> {
+        match self {
+            Status::Error(message) => Some(message),
+            _ => None,
+        }
+    }
+}
+
+/// Result of solving one puzzle
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub file: PathBuf,
+    pub status: Status,
+    pub width: usize,
+    pub height: usize,
+    /// Number of steps of each algorithm, in the solver order. A step is one proposition
+    /// of [`Solver::step`], the solver looking at every line again after each of them.
+    pub steps: Vec<(&'static str, usize)>,
+    pub duration: Duration,
+    /// Cells left neither filled nor crossed
+    pub unknown: usize,
+}
+
+/// Solve the puzzle step by step, counting the steps of each algorithm
+pub fn solve_file(solver: &Solver, file: &Path) -> Report {
+    let mut steps: Vec<(&'static str, usize)> =
+        solver.algo_names().into_iter().map(|name| (name, 0)).collect();
+    let start = Instant::now();
+    let mut game = match Game::new(file) {
+        Ok(game) => game,
+        Err(error) => {
+            return Report {
+                file: file.to_path_buf(),
+                status: Status::Error(error.to_string()),
+                width: 0,
+                height: 0,
+                steps,
+                duration: start.elapsed(),
+                unknown: 0,
+            }
+        }
+    };
+    let mut contradiction = false;
+    loop {
+        match solver.step(&game) {
+            Ok(Some((name, proposition))) => {
+                proposition.merge(&mut game.board);
+                if let Some(step) = steps.iter_mut().find(|(n, _)| *n == name) {
+                    step.1 += 1;
+                }
+            }
+            Ok(None) => break,
+            Err(_) => {
+                contradiction = true;
+                break;
+            }
+        }
+    }
+    let duration = start.elapsed();
+    let status = if contradiction {
+        Status::Contradiction
+    } else if game.is_finished() {
+        Status::Solved
+    } else {
+        Status::Stuck
+    };
+    Report {
+        file: file.to_path_buf(),
+        status,
+        width: game.board.width(),
+        height: game.board.height(),
+        steps,
+        duration,
+        unknown: game.board.count_unknown(),
+    }
+}
+
+/// Solve every PNG, text and JSON (with the `serde` feature) puzzle of the directory,
+/// sorted by file name
+pub fn solve_dir<P>(dir: P) -> io::Result<Vec<Report>>
+where
+    P: AsRef<Path>,
+{
+    let mut files = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_puzzle = path.extension().is_some_and(|ext| {
+            ext.eq_ignore_ascii_case("png")
+                || ext.eq_ignore_ascii_case("txt")
+                || (cfg!(feature = "serde") && ext.eq_ignore_ascii_case("json"))
+        });
+        if path.is_file() && is_puzzle {
+            files.push(path);
+        }
+    }
+    files.sort();
+
+    let solver = SolverBuilder::new().build();
+    Ok(files.iter().map(|file| solve_file(&solver, file)).collect())
+}
+
+fn json_string(s: &str) -> String {
+    let mut json = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// Write the reports as a JSON array
+pub fn to_json(reports: &[Report]) -> String {
+    let mut json = String::from("[\n");
+    for (i, report) in reports.iter().enumerate() {
+        let steps: Vec<String> = report
+            .steps
+            .iter()
+            .map(|(name, count)| format!("{}: {}", json_string(name), count))
+            .collect();
+        let error = report.status.error().map_or("null".to_string(), json_string);
+        // Writing to a String can't fail
+        let _ = write!(
+            json,
+            "  {{\"file\": {}, \"status\": \"{}\", \"error\": {}, \"width\": {}, \"height\": {}, \"steps\": {{{}}}, \"time_ms\": {:.3}, \"unknown\": {}}}",
+            json_string(&report.file.to_string_lossy()),
+            report.status.as_str(),
+            error,
+            report.width,
+            report.height,
+            steps.join(", "),
+            report.duration.as_secs_f64() * 1000.0,
+            report.unknown
+        );
+        json.push_str(if i + 1 < reports.len() { ",\n" } else { "\n" });
+    }
+    json.push_str("]\n");
+    json
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// Write the reports as CSV, with one `steps_<algo>` column per algorithm
+pub fn to_csv(reports: &[Report]) -> String {
+    let mut csv = String::from("file,status,error,width,height,time_ms,unknown");
+    let names: Vec<&str> = reports
+        .first()
+        .map(|report| report.steps.iter().map(|(name, _)| *name).collect())
+        .unwrap_or_default();
+    for name in &names {
+        csv.push_str(",steps_");
+        csv.push_str(name);
+    }
+    csv.push('\n');
+    for report in reports {
+        let _ = write!(
+            csv,
+            "{},{},{},{},{},{:.3},{}",
+            csv_field(&report.file.to_string_lossy()),
+            report.status.as_str(),
+            csv_field(report.status.error().unwrap_or_default()),
+            report.width,
+            report.height,
+            report.duration.as_secs_f64() * 1000.0,
+            report.unknown
+        );
+        for (_, count) in &report.steps {
+            let _ = write!(csv, ",{}", count);
+        }
+        csv.push('\n');
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_solves_a_directory() {
+        let reports = solve_dir("test").unwrap();
+        assert_eq!(reports.len(), 5);
+        assert_eq!(reports[0].file, Path::new("test").join("3x7-key.png"));
+        for report in &reports {
+            assert_eq!(report.status, Status::Solved);
+            assert_eq!(report.unknown, 0);
+            assert!(report.steps.iter().map(|(_, count)| count).sum::<usize>() > 0);
+        }
+
+        let json = to_json(&reports);
+        assert!(json.contains(r#""status": "solved", "error": null, "width": 4, "height": 4, "steps": {"FullLine": "#));
+
+        let csv = to_csv(&reports);
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some("file,status,error,width,height,time_ms,unknown,steps_FullLine,steps_Placements,steps_ColorPlacements,steps_Domains")
+        );
+        assert!(lines.next().unwrap().contains("3x7-key.png,solved,,3,7,"));
+    }
+
+    #[test]
+    fn it_reports_the_puzzles_that_cant_be_loaded() {
+        let solver = SolverBuilder::new().build();
+        let report = solve_file(&solver, Path::new("test/missing.png"));
+        let message = match &report.status {
+            Status::Error(message) => message.clone(),
+            status => panic!("unexpected status {:?}", status),
+        };
+        assert!(!message.is_empty());
+
+        let reports = [report];
+        assert!(to_json(&reports).contains(&format!(r#""status": "error", "error": {},"#, json_string(&message))));
+        assert!(to_csv(&reports).contains(&format!("missing.png,error,{},0,0,", csv_field(&message))));
+    }
+}
@@ -1,16 +1,22 @@
 use std::fmt::{Display, self};
 
-use image::Rgb;
-
-use crate::picross_image::WHITE;
+use crate::palette::BLANK;
 
 
 #[derive(Clone, Eq, PartialEq, Copy, Debug)]
 pub enum Pixel {
-    Color(Rgb<u8>),
+    /// Index of the color in the palette of the puzzle, the blank color being an
+    /// unknown cell
+    Color(u8),
     Cross
 }
 
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "crate::json::BoardData", into = "crate::json::BoardData")
+)]
 pub struct Board {
     img: Vec<Pixel>,
     width:usize,
@@ -20,7 +26,7 @@ pub struct Board {
 impl Board {
     pub fn new(width: usize, height: usize) -> Self {
         Board {
-            img: vec![Pixel::Color(WHITE); width*height],
+            img: vec![Pixel::Color(BLANK); width*height],
             width,
             height
         }
@@ -34,14 +40,43 @@ impl Board {
         self.height
     }
 
+    /// The cell at (x, y).
+    ///
+    /// Panics if the cell is outside of the board.
     pub fn get_pixel(&self, x: usize, y: usize) -> &Pixel {
-        self.img.get(x + y * self.width).unwrap()
+        match self.get(x, y) {
+            Some(pixel) => pixel,
+            None => panic!("cell ({}, {}) outside of the {}x{} board", x, y, self.width, self.height),
+        }
+    }
+
+    /// The cell at (x, y), or `None` outside of the board
+    pub fn get(&self, x: usize, y: usize) -> Option<&Pixel> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        Some(&self.img[x + y * self.width])
+    }
+
+    /// Count the cells that are neither filled nor crossed
+    pub fn count_unknown(&self) -> usize {
+        self.img.iter().filter(|p| **p == Pixel::Color(BLANK)).count()
+    }
+
+    /// The cells of the row `y`, from left to right
+    pub fn row(&self, y: usize) -> Vec<Pixel> {
+        (0..self.width).map(|x| *self.get_pixel(x, y)).collect()
     }
 
+    /// The cells of the column `x`, from top to bottom
+    pub fn column(&self, x: usize) -> Vec<Pixel> {
+        (0..self.height).map(|y| *self.get_pixel(x, y)).collect()
+    }
+
+    /// Change the cell at (x, y), the cells outside of the board being ignored
     pub fn set_pixel(&mut self, x: usize, y: usize, pix: &Pixel) {
-        match self.img.get_mut(x + y * self.width) {
-            Some(p) => *p = *pix,
-            None => {}
+        if x < self.width && y < self.height {
+            self.img[x + y * self.width] = *pix;
         }
     }
 }
@@ -54,7 +89,7 @@ impl Display for Board {
                 let c = match self.get_pixel(x, y) {
                     Pixel::Color(color) => {
                         match color {
-                            &WHITE => " ",
+                            &BLANK => " ",
                             _ => "█"
 
                         }
@@ -65,8 +100,30 @@ impl Display for Board {
                 };
                 write!(f, "{}", c)?;
             }
-            write!(f, "\n")?;
+            writeln!(f)?;
         }
         write!(f, "=======================")
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_ignores_the_cells_outside_of_the_board() {
+        let mut board = Board::new(2, 2);
+        board.set_pixel(2, 0, &Pixel::Color(1));
+        board.set_pixel(0, usize::MAX, &Pixel::Color(1));
+        assert_eq!(board.count_unknown(), 4);
+        assert_eq!(board.get(1, 1), Some(&Pixel::Color(BLANK)));
+        assert_eq!(board.get(2, 0), None);
+        assert_eq!(board.get(usize::MAX, usize::MAX), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "outside of the 2x2 board")]
+    fn it_panics_on_a_pixel_outside_of_the_board() {
+        // (2, 0) would be the cell (0, 1) without the check
+        Board::new(2, 2).get_pixel(2, 0);
+    }
+}
@@ -1,31 +1,144 @@
 use std::fmt;
 use std::error;
 use std::path::Path;
+use std::time::{Duration, Instant};
 
-use crate::{picross_image::{Image, ImageError}, board::Board};
+use crate::{palette::BLANK, picross_image::{Clue, Image, ImageError}, board::{Board, Pixel}, solver::{Contradiction, GameView, Proposition, SolverBuilder}};
 
 pub struct Game {
     pub image : Image,
-    pub board: Board
+    pub board: Board,
+    pub assists: Assists,
+    /// The changes of the board, with the previous state of the cells, to undo them
+    history: Vec<Vec<(usize, usize, Pixel)>>,
+    mode: Mode,
+    mistake_count: u32,
+    started: Instant,
+    /// The playing time, once the game is won or lost
+    stopped: Option<Duration>,
+    subscribers: Vec<Subscriber>
 }
 
+type Subscriber = Box<dyn FnMut(&Event)>;
+
+/// How the moves of the player are checked
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// Every move is allowed, the mistakes are only shown when asked
+    #[default]
+    Free,
+    /// Each fill is checked against the solution. A wrong fill is a mistake : the cell is
+    /// revealed, the penalty is added to the time, and the game is lost after
+    /// `max_mistakes` mistakes.
+    Penalty { max_mistakes: u32, penalty: Duration },
+}
+
+/// What happened during a move, for the front-ends to react
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// The cell at (x, y) has a new state
+    CellChanged { x: usize, y: usize, pixel: Pixel },
+    /// The filled cells of the line match its clues
+    LineCompleted { view: GameView, index: usize },
+    /// The board is the solution of the puzzle
+    Solved,
+    /// A wrong fill at (x, y), with the number of mistakes so far
+    Mistake { x: usize, y: usize, count: u32 },
+    /// Too many mistakes were made
+    Lost,
+}
+
+/// The help given to the player, chosen for each game
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Assists {
+    /// Cross the remaining cells of a row or column once its filled cells match the clues
+    pub auto_cross: bool,
+}
+
+/// A help for the next move of the player
+pub enum Hint {
+    /// Cells that can be deduced from the board, and why
+    Deduction {
+        proposition: Proposition,
+        explanation: String,
+    },
+    /// A line of the board which can't match its clues
+    Mistake(Contradiction),
+}
+
+impl Hint {
+    /// The row or column of the hint
+    pub fn line(&self) -> (GameView, usize) {
+        match self {
+            Hint::Deduction { proposition, .. } => (proposition.view(), proposition.index()),
+            Hint::Mistake(contradiction) => (contradiction.view, contradiction.index),
+        }
+    }
+}
+
+impl fmt::Display for Hint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Hint::Deduction { explanation, .. } => write!(f, "{}", explanation),
+            Hint::Mistake(contradiction) => write!(f, "{}", contradiction),
+        }
+    }
+}
+
+/// Why the clues of a puzzle can't be solved, whatever the board
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClueError {
+    /// A clue of the line has no cell, or a color that isn't in the palette
+    InvalidClue { view: GameView, index: usize },
+    /// The clues of the line and the gaps between them are longer than the line
+    TooLong { view: GameView, index: usize },
+    /// The rows and the columns don't have the same number of cells of this color
+    ColorCount { color: u8, rows: u64, cols: u64 },
+}
+
+impl fmt::Display for ClueError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ClueError::InvalidClue { view, index } => write!(f, "Invalid clue on {} {}", view, index),
+            ClueError::TooLong { view, index } => {
+                write!(f, "The clues of {} {} don't fit in the line", view, index)
+            }
+            ClueError::ColorCount { color, rows, cols } => write!(
+                f,
+                "The rows have {} cells of the color {}, and the columns {}",
+                rows, color, cols
+            ),
+        }
+    }
+}
+
+impl error::Error for ClueError {}
+
 #[derive(Debug)]
 pub enum GameError {
-    ImageError(ImageError)
+    ImageError(ImageError),
+    /// The clues of the puzzle can't be solved
+    InvalidClues(ClueError),
+    /// The mode needs the solution of the puzzle
+    NoSolution
 }
 
 impl fmt::Display for GameError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match &*self {
-            GameError::ImageError(e) => write!(f, "{}", e.to_string())
+        match self {
+            GameError::ImageError(e) => write!(f, "{}", e),
+            GameError::InvalidClues(e) => write!(f, "{}", e),
+            GameError::NoSolution => write!(f, "The puzzle has no solution to check the moves")
         }
     }
 }
 
 impl error::Error for GameError {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
-        match &*self {
-            GameError::ImageError(ref e) => Some(e)
+        match self {
+            GameError::ImageError(ref e) => Some(e),
+            GameError::InvalidClues(ref e) => Some(e),
+            GameError::NoSolution => None
         }
     }
 }
@@ -35,21 +148,633 @@ impl From<ImageError> for GameError {
     }
 }
 
+impl From<ClueError> for GameError {
+    fn from(err: ClueError) -> GameError {
+        GameError::InvalidClues(err)
+    }
+}
+
 type Result<T> = std::result::Result<T, GameError>;
 
 impl Game {
     pub fn new<P>(filename: P) -> Result<Game>
     where P: AsRef<Path> {
-        let image = Image::from_image(filename)?;
+        let image = Image::open(filename)?;
+        Game::try_with_image(image)
+    }
+
+    /// Start a new game like [`Game::with_image`], checking first that the clues of the
+    /// puzzle can be solved, as clues read from a file may not
+    pub fn try_with_image(image: Image) -> Result<Game> {
+        check_clues(&image)?;
+        Ok(Game::with_image(image))
+    }
+
+    /// Start a new game with an empty board for the given puzzle
+    pub fn with_image(image: Image) -> Game {
         let width = image.width as usize;
         let height = image.height as usize;
-        Ok(Game {
+        Game {
             image,
-            board: Board::new(width, height)
-        })
+            board: Board::new(width, height),
+            assists: Assists::default(),
+            history: vec![],
+            mode: Mode::Free,
+            mistake_count: 0,
+            started: Instant::now(),
+            stopped: None,
+            subscribers: vec![]
+        }
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Change the mode, the penalty mode needing the solution of the puzzle
+    pub fn set_mode(&mut self, mode: Mode) -> Result<()> {
+        if mode != Mode::Free && !self.image.has_solution() {
+            return Err(GameError::NoSolution);
+        }
+        self.mode = mode;
+        Ok(())
+    }
+
+    /// Number of wrong fills, in the penalty mode
+    pub fn mistake_count(&self) -> u32 {
+        self.mistake_count
+    }
+
+    /// The playing time, with the penalties. The time stops when the game is won or lost.
+    pub fn elapsed(&self) -> Duration {
+        let time = self.stopped.unwrap_or_else(|| self.started.elapsed());
+        match self.mode {
+            Mode::Free => time,
+            Mode::Penalty { penalty, .. } => time + penalty * self.mistake_count,
+        }
+    }
+
+    /// Whether too many mistakes were made
+    pub fn is_lost(&self) -> bool {
+        match self.mode {
+            Mode::Free => false,
+            Mode::Penalty { max_mistakes, .. } => self.mistake_count >= max_mistakes,
+        }
+    }
+
+    /// The cell of the solution, if the puzzle has one
+    fn solution(&self, x: usize, y: usize) -> Option<Pixel> {
+        let color = self.image.solution(x as u32, y as u32)?;
+        Some(if color == BLANK { Pixel::Cross } else { Pixel::Color(color) })
+    }
+
+    /// Call `subscriber` with every event of the game
+    pub fn subscribe<F>(&mut self, subscriber: F)
+    where
+        F: FnMut(&Event) + 'static,
+    {
+        self.subscribers.push(Box::new(subscriber));
+    }
+
+    fn emit(&mut self, event: Event) {
+        for subscriber in &mut self.subscribers {
+            subscriber(&event);
+        }
+    }
+
+    fn is_line_completed(&self, view: GameView, index: usize) -> bool {
+        match view {
+            GameView::Row => blocks(&self.board.row(index)) == self.image.rows[index],
+            GameView::Column => blocks(&self.board.column(index)) == self.image.cols[index],
+        }
+    }
+
+    /// Set the cells, and return their previous state
+    fn set_cells(&mut self, cells: &[(usize, usize, Pixel)]) -> Vec<(usize, usize, Pixel)> {
+        let mut lines: Vec<(GameView, usize)> = vec![];
+        for &(x, y, _) in cells {
+            lines.push((GameView::Row, y));
+            lines.push((GameView::Column, x));
+        }
+        lines.sort_by_key(|&(view, index)| (view == GameView::Column, index));
+        lines.dedup();
+        let completed: Vec<bool> =
+            lines.iter().map(|&(view, index)| self.is_line_completed(view, index)).collect();
+
+        let mut previous = vec![];
+        for &(x, y, pixel) in cells {
+            previous.push((x, y, *self.board.get_pixel(x, y)));
+            self.board.set_pixel(x, y, &pixel);
+            self.emit(Event::CellChanged { x, y, pixel });
+        }
+        for (&(view, index), was_completed) in lines.iter().zip(completed) {
+            if !was_completed && self.is_line_completed(view, index) {
+                self.emit(Event::LineCompleted { view, index });
+            }
+        }
+        previous
+    }
+
+    /// Change the cells that are not already set, as one step of the history. The cells
+    /// outside of the board, from a proposition made for another game, are ignored.
+    fn change(&mut self, cells: Vec<(usize, usize, Pixel)>) {
+        let cells: Vec<(usize, usize, Pixel)> = cells
+            .into_iter()
+            .filter(|&(x, y, pixel)| self.board.get(x, y).is_some_and(|cell| *cell != pixel))
+            .collect();
+        if !cells.is_empty() {
+            let previous = self.set_cells(&cells);
+            self.history.push(previous);
+        }
+    }
+
+    /// Set a cell that stays on the board : it isn't recorded, and the earlier changes of
+    /// the history leave it alone when they are undone
+    fn reveal(&mut self, x: usize, y: usize, pixel: Pixel) {
+        if *self.board.get_pixel(x, y) != pixel {
+            self.set_cells(&[(x, y, pixel)]);
+        }
+        for cells in &mut self.history {
+            cells.retain(|&(cx, cy, _)| (cx, cy) != (x, y));
+        }
+        self.history.retain(|cells| !cells.is_empty());
+    }
+
+    /// Stop the game once it is won or lost
+    fn check_end(&mut self) {
+        if self.stopped.is_some() {
+            return;
+        }
+        if self.is_lost() {
+            self.stopped = Some(self.started.elapsed());
+            self.emit(Event::Lost);
+        } else if self.is_finished() {
+            self.stopped = Some(self.started.elapsed());
+            self.emit(Event::Solved);
+        }
+    }
+
+    /// Play the cell at (x, y), then apply the assists. Nothing can be played once the
+    /// game is won or lost.
+    ///
+    /// Each change of the board can be undone, the assists being undone separately. A cell
+    /// revealed by a mistake can't be undone, like the mistake itself.
+    ///
+    /// A cell outside of the board, or a color outside of the palette, is ignored.
+    pub fn play(&mut self, x: usize, y: usize, pixel: Pixel) {
+        if x >= self.board.width() || y >= self.board.height() || self.stopped.is_some() {
+            return;
+        }
+        if matches!(pixel, Pixel::Color(color) if color as usize >= self.image.palette.len()) {
+            return;
+        }
+        let mut pixel = pixel;
+        let mut revealed = false;
+        if let (Mode::Penalty { .. }, Some(solution)) = (self.mode, self.solution(x, y)) {
+            let filled = matches!(pixel, Pixel::Color(color) if color != BLANK);
+            if filled && pixel != solution {
+                self.mistake_count += 1;
+                self.emit(Event::Mistake { x, y, count: self.mistake_count });
+                pixel = solution;
+                revealed = true;
+            }
+        }
+        if revealed {
+            self.reveal(x, y, pixel);
+        } else {
+            self.change(vec![(x, y, pixel)]);
+        }
+
+        let filled = matches!(pixel, Pixel::Color(color) if color != BLANK);
+        if self.assists.auto_cross && filled {
+            let mut crossed = vec![];
+            if self.is_line_completed(GameView::Row, y) {
+                crossed.extend((0..self.board.width()).map(|x| (x, y, Pixel::Cross)));
+            }
+            if self.is_line_completed(GameView::Column, x) {
+                crossed.extend((0..self.board.height()).map(|y| (x, y, Pixel::Cross)));
+            }
+            crossed.retain(|&(x, y, _)| *self.board.get_pixel(x, y) == Pixel::Color(BLANK));
+            self.change(crossed);
+        }
+        self.check_end();
+    }
+
+    /// Fill the cell at (x, y) with the color at this index of the palette
+    pub fn fill(&mut self, x: usize, y: usize, color: u8) {
+        self.play(x, y, Pixel::Color(color));
+    }
+
+    /// Cross the cell at (x, y)
+    pub fn cross(&mut self, x: usize, y: usize) {
+        self.play(x, y, Pixel::Cross);
+    }
+
+    /// Clear the cell at (x, y)
+    pub fn clear(&mut self, x: usize, y: usize) {
+        self.play(x, y, Pixel::Color(BLANK));
+    }
+
+    /// Apply a proposition of the solver, as one step of the history
+    pub fn apply(&mut self, proposition: &Proposition) {
+        if self.stopped.is_some() {
+            return;
+        }
+        self.change(proposition.cells());
+        self.check_end();
+    }
+
+    /// Undo the last change of the board. Returns false if there is nothing to undo, or
+    /// once the game is won or lost.
+    pub fn undo(&mut self) -> bool {
+        if self.stopped.is_some() {
+            return false;
+        }
+        match self.history.pop() {
+            Some(cells) => {
+                self.set_cells(&cells);
+                true
+            }
+            None => false,
+        }
     }
 
     pub fn is_finished(&self) -> bool {
         self.board.eq(&self.image)
     }
+
+    /// The rows and columns of the board which can't match their clues anymore
+    pub fn mistakes(&self) -> Vec<Contradiction> {
+        SolverBuilder::new().build().check(self)
+    }
+
+    /// Find the next move from the board of the player, not from the solution. A mistake
+    /// on the board is pointed out first. Returns `None` if the solver is stuck.
+    pub fn hint(&self) -> Option<Hint> {
+        if let Some(mistake) = self.mistakes().into_iter().next() {
+            return Some(Hint::Mistake(mistake));
+        }
+        match SolverBuilder::new().build().hint(self) {
+            Ok(Some((explanation, proposition))) => Some(Hint::Deduction { proposition, explanation }),
+            Ok(None) => None,
+            Err(contradiction) => Some(Hint::Mistake(contradiction)),
+        }
+    }
+
+    /// For each clue of the row or column, whether a block of the board surely matches it
+    pub fn satisfied_clues(&self, view: GameView, index: usize) -> Vec<bool> {
+        match view {
+            GameView::Row => satisfied_clues(&self.image.rows[index], &self.board.row(index)),
+            GameView::Column => satisfied_clues(&self.image.cols[index], &self.board.column(index)),
+        }
+    }
+}
+
+/// Check that the clues of the puzzle can be solved : each line of clues, then the number
+/// of cells of each color. The clues read from a file may not.
+pub fn check_clues(image: &Image) -> std::result::Result<(), ClueError> {
+    let invalid = |clue: &Clue| {
+        clue.count == 0 || clue.color == BLANK || clue.color as usize >= image.palette.len()
+    };
+    let rows = image.rows.iter().enumerate().map(|(index, clues)| (GameView::Row, index, clues));
+    let cols = image.cols.iter().enumerate().map(|(index, clues)| (GameView::Column, index, clues));
+    for (view, index, clues) in rows.chain(cols) {
+        if clues.iter().any(invalid) {
+            return Err(ClueError::InvalidClue { view, index });
+        }
+        // Blocks of the same color need a gap between them
+        let gaps = clues.windows(2).filter(|pair| pair[0].color == pair[1].color).count() as u64;
+        let length = clues.iter().map(|clue| u64::from(clue.count)).sum::<u64>() + gaps;
+        let len = match view {
+            GameView::Row => image.width,
+            GameView::Column => image.height,
+        };
+        if length > u64::from(len) {
+            return Err(ClueError::TooLong { view, index });
+        }
+    }
+
+    let count = |lines: &[Vec<Clue>], color: u8| -> u64 {
+        let clues = lines.iter().flatten().filter(|clue| clue.color == color);
+        clues.map(|clue| u64::from(clue.count)).sum()
+    };
+    for color in image.palette.indices() {
+        let (rows, cols) = (count(&image.rows, color), count(&image.cols, color));
+        if rows != cols {
+            return Err(ClueError::ColorCount { color, rows, cols });
+        }
+    }
+    Ok(())
+}
+
+/// The blocks of filled cells of the line, as clues
+pub fn blocks(line: &[Pixel]) -> Vec<Clue> {
+    let mut blocks: Vec<Clue> = vec![];
+    let mut previous = Pixel::Cross;
+    for pixel in line {
+        match pixel {
+            Pixel::Color(color) if *color != BLANK => match blocks.last_mut() {
+                Some(block) if previous == *pixel => block.count += 1,
+                _ => blocks.push(Clue { color: *color, count: 1 }),
+            },
+            _ => {}
+        }
+        previous = *pixel;
+    }
+    blocks
+}
+
+/// Count the clues matched by the blocks at the start of the line, up to the first
+/// unknown cell : these blocks can't grow anymore
+fn anchored<'a, C, L>(mut clues: C, line: L) -> usize
+where
+    C: Iterator<Item = &'a Clue>,
+    L: Iterator<Item = &'a Pixel>,
+{
+    let mut matched = 0;
+    let mut block: Option<Clue> = None;
+    for pixel in line {
+        let color = match pixel {
+            Pixel::Color(color) if *color == BLANK => return matched,
+            Pixel::Color(color) => Some(*color),
+            Pixel::Cross => None,
+        };
+        if let Some(current) = &mut block {
+            if Some(current.color) == color {
+                current.count += 1;
+                continue;
+            }
+            if clues.next() != block.as_ref() {
+                return matched;
+            }
+            matched += 1;
+        }
+        block = color.map(|color| Clue { color, count: 1 });
+    }
+    if block.is_some() && clues.next() == block.as_ref() {
+        matched += 1;
+    }
+    matched
+}
+
+/// For each clue, whether a block of the line surely matches it
+pub(crate) fn satisfied_clues(clues: &[Clue], line: &[Pixel]) -> Vec<bool> {
+    if blocks(line) == clues {
+        return vec![true; clues.len()];
+    }
+    let left = anchored(clues.iter(), line.iter());
+    let right = anchored(clues.iter().rev(), line.iter().rev()).min(clues.len() - left);
+    (0..clues.len()).map(|j| j < left || j >= clues.len() - right).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use crate::board::Pixel;
+    use crate::solver::{GameLine, GameView, Reason};
+    use crate::text;
+
+    use super::*;
+
+    const BLACK: Pixel = Pixel::Color(1);
+
+    #[test]
+    fn it_rejects_clues_that_cant_be_solved() {
+        let image = text::parse_image("#r\n.#\n").unwrap();
+        let error = |rows: Vec<Vec<Clue>>, cols: Vec<Vec<Clue>>| {
+            match Game::try_with_image(Image::from_clues(rows, cols, image.palette.clone())) {
+                Err(GameError::InvalidClues(error)) => Some(error),
+                _ => None,
+            }
+        };
+        assert!(error(image.rows.clone(), image.cols.clone()).is_none());
+
+        // The two black cells of the first column don't fit with a gap between them
+        let mut cols = image.cols.clone();
+        cols[0] = vec![Clue::new(1, 1), Clue::new(1, 1)];
+        assert_eq!(error(image.rows.clone(), cols), Some(ClueError::TooLong { view: GameView::Column, index: 0 }));
+
+        // A black cell moved to a red one in the rows only
+        let mut rows = image.rows.clone();
+        rows[1] = vec![Clue::new(2, 1)];
+        assert_eq!(error(rows, image.cols.clone()), Some(ClueError::ColorCount { color: 1, rows: 1, cols: 2 }));
+
+        let mut rows = image.rows.clone();
+        rows[1] = vec![Clue::new(3, 1)];
+        assert_eq!(error(rows, image.cols.clone()), Some(ClueError::InvalidClue { view: GameView::Row, index: 1 }));
+    }
+
+    #[test]
+    fn it_finds_the_mistakes_from_the_clues() {
+        let image = text::parse_image("##.\n...\n#.#\n").unwrap();
+        let mut game = Game::with_image(Image::from_clues(image.rows, image.cols, image.palette));
+        assert!(game.mistakes().is_empty());
+
+        game.board = text::parse_board("#.?\n?#?\n.??\n", &game.image.palette).unwrap();
+        let mistakes = game.mistakes();
+        let lines: Vec<(GameView, usize)> = mistakes.iter().map(|m| (m.view, m.index)).collect();
+        assert_eq!(
+            lines,
+            vec![
+                (GameView::Row, 0),
+                (GameView::Row, 1),
+                (GameView::Row, 2),
+                (GameView::Column, 0)
+            ]
+        );
+        assert_eq!(mistakes[0].reason, Reason::NoPlacement);
+
+        game.board.set_pixel(1, 0, &BLACK);
+        game.board.set_pixel(1, 1, &Pixel::Cross);
+        game.board.set_pixel(0, 2, &BLACK);
+        assert!(game.mistakes().is_empty());
+    }
+
+    #[test]
+    fn it_finds_the_satisfied_clues() {
+        let image = text::parse_image("#.##.#\n.#....\n").unwrap();
+        let mut game = Game::with_image(image);
+        assert_eq!(game.satisfied_clues(GameView::Row, 0), vec![false, false, false]);
+
+        game.board = text::parse_board("#.??.#\n??????\n", &game.image.palette).unwrap();
+        assert_eq!(game.satisfied_clues(GameView::Row, 0), vec![true, false, true]);
+        assert_eq!(game.satisfied_clues(GameView::Column, 0), vec![true]);
+        assert_eq!(game.satisfied_clues(GameView::Column, 5), vec![true]);
+
+        // The blocks may still grow
+        game.board = text::parse_board("#.##??\n??????\n", &game.image.palette).unwrap();
+        assert_eq!(game.satisfied_clues(GameView::Row, 0), vec![true, false, false]);
+        game.board = text::parse_board("#.???#\n??????\n", &game.image.palette).unwrap();
+        assert_eq!(game.satisfied_clues(GameView::Row, 0), vec![true, false, false]);
+
+        // Every block is there
+        game.board = text::parse_board("#?##?#\n?#????\n", &game.image.palette).unwrap();
+        assert_eq!(game.satisfied_clues(GameView::Row, 0), vec![true, true, true]);
+        assert_eq!(game.satisfied_clues(GameView::Row, 1), vec![true]);
+
+        // A block that doesn't match its clue stops the matching
+        game.board = text::parse_board("##.???\n??????\n", &game.image.palette).unwrap();
+        assert_eq!(game.satisfied_clues(GameView::Row, 0), vec![false, false, false]);
+    }
+
+    #[test]
+    fn it_gives_hints_from_the_board() {
+        let mut game = Game::new("test/4x4-shuriken.png").unwrap();
+        let hint = game.hint().unwrap();
+        assert_eq!(hint.line(), (GameView::Row, 0));
+        assert!(matches!(hint, Hint::Deduction { .. }));
+        assert_eq!(hint.to_string(), "The clues of row 0 and the gaps between them fill the whole line");
+
+        game.play(2, 0, BLACK);
+        let hint = game.hint().unwrap();
+        assert_eq!(hint.line(), (GameView::Row, 0));
+        assert!(matches!(hint, Hint::Mistake(_)));
+
+        game.board = Board::from(&game.image);
+        assert!(game.hint().is_none());
+    }
+
+    /// Keep the events of the game
+    fn record(game: &mut Game) -> Rc<RefCell<Vec<Event>>> {
+        let events = Rc::new(RefCell::new(vec![]));
+        let recorder = Rc::clone(&events);
+        game.subscribe(move |event| recorder.borrow_mut().push(event.clone()));
+        events
+    }
+
+    #[test]
+    fn it_sends_the_events_of_the_actions() {
+        let mut game = Game::with_image(text::parse_image("#.\n.#\n").unwrap());
+        let events = record(&mut game);
+        let black = 1;
+
+        game.fill(0, 0, black);
+        assert_eq!(
+            events.take(),
+            vec![
+                Event::CellChanged { x: 0, y: 0, pixel: BLACK },
+                Event::LineCompleted { view: GameView::Row, index: 0 },
+                Event::LineCompleted { view: GameView::Column, index: 0 }
+            ]
+        );
+
+        game.cross(1, 0);
+        game.undo();
+        assert_eq!(
+            events.take(),
+            vec![
+                Event::CellChanged { x: 1, y: 0, pixel: Pixel::Cross },
+                Event::CellChanged { x: 1, y: 0, pixel: Pixel::Color(BLANK) }
+            ]
+        );
+
+        let proposition = SolverBuilder::new().build().solve(&game).unwrap().unwrap();
+        game.apply(&proposition);
+        assert_eq!(events.take(), vec![Event::CellChanged { x: 1, y: 0, pixel: Pixel::Cross }]);
+
+        // The cells of a proposition outside of the board are ignored
+        let cells = [Pixel::Color(BLANK); 2];
+        let line = GameLine::new(GameView::Row, 5, cells.iter().collect(), &[]);
+        game.apply(&Proposition::new(vec![Some(BLACK); 2], &line));
+        assert!(events.take().is_empty());
+
+        // As are the cells outside of the board, and the colors outside of the palette
+        game.fill(2, 1, black);
+        game.fill(1, 1, 2);
+        assert!(events.take().is_empty());
+        assert_eq!(*game.board.get_pixel(1, 1), Pixel::Color(BLANK));
+
+        game.fill(1, 1, black);
+        assert_eq!(events.borrow().last(), Some(&Event::Solved));
+        assert!(game.is_finished());
+
+        // Nothing can be played or undone once the game is won
+        game.clear(1, 1);
+        assert_eq!(*game.board.get_pixel(1, 1), BLACK);
+        assert!(!game.undo());
+        assert!(game.is_finished());
+    }
+
+    #[test]
+    fn it_counts_the_mistakes_in_the_penalty_mode() {
+        let image = text::parse_image("#.\n.#\n").unwrap();
+        let mut game = Game::with_image(Image::from_clues(image.rows.clone(), image.cols.clone(), image.palette.clone()));
+        let penalty = Mode::Penalty { max_mistakes: 2, penalty: Duration::from_secs(60) };
+        assert!(matches!(game.set_mode(penalty), Err(GameError::NoSolution)));
+
+        let mut game = Game::with_image(image);
+        game.set_mode(penalty).unwrap();
+        let events = record(&mut game);
+        let mistakes = || -> Vec<Event> {
+            let events = events.take();
+            events.into_iter().filter(|e| matches!(e, Event::Mistake { .. } | Event::Lost)).collect()
+        };
+        game.play(0, 0, BLACK);
+        // Crosses are not checked
+        game.play(1, 1, Pixel::Cross);
+        assert!(mistakes().is_empty());
+
+        // The wrong fill reveals the cell
+        game.play(1, 0, BLACK);
+        assert_eq!(mistakes(), vec![Event::Mistake { x: 1, y: 0, count: 1 }]);
+        assert_eq!(*game.board.get_pixel(1, 0), Pixel::Cross);
+        assert!(game.elapsed() >= Duration::from_secs(60));
+        assert!(!game.is_lost());
+
+        game.play(0, 1, BLACK);
+        assert_eq!(mistakes(), vec![Event::Mistake { x: 0, y: 1, count: 2 }, Event::Lost]);
+        assert!(game.is_lost());
+        assert_eq!(game.mistake_count(), 2);
+        game.play(1, 1, BLACK);
+        assert_eq!(*game.board.get_pixel(1, 1), Pixel::Cross);
+        assert!(!game.undo());
+        assert_eq!(*game.board.get_pixel(0, 1), Pixel::Cross);
+    }
+
+    #[test]
+    fn it_keeps_the_cells_revealed_by_the_mistakes() {
+        let mut game = Game::with_image(text::parse_image("#.\n.#\n").unwrap());
+        game.set_mode(Mode::Penalty { max_mistakes: 3, penalty: Duration::from_secs(1) }).unwrap();
+        game.play(0, 0, BLACK);
+
+        // The wrong fill of (1, 0) reveals a cross, which the undo leaves alone
+        game.play(1, 0, BLACK);
+        assert_eq!(game.mistake_count(), 1);
+        assert!(game.undo());
+        assert_eq!(text::board_to_text(&game.board, &game.image.palette).unwrap(), "?.\n??\n");
+        assert!(!game.undo());
+
+        // Even when an earlier change crossed the revealed cell
+        game.play(0, 1, Pixel::Cross);
+        game.play(0, 1, BLACK);
+        assert_eq!(game.mistake_count(), 2);
+        assert_eq!(text::board_to_text(&game.board, &game.image.palette).unwrap(), "?.\n.?\n");
+        assert!(!game.undo());
+    }
+
+    #[test]
+    fn it_crosses_the_completed_lines() {
+        let image = text::parse_image("#.##.#\n.#....\n").unwrap();
+        let mut game = Game::with_image(image.clone());
+        game.play(1, 1, BLACK);
+        assert_eq!(text::board_to_text(&game.board, &game.image.palette).unwrap(), "??????\n?#????\n");
+
+        let mut game = Game::with_image(image);
+        game.assists.auto_cross = true;
+        game.play(1, 1, BLACK);
+        assert_eq!(text::board_to_text(&game.board, &game.image.palette).unwrap(), "?.????\n.#....\n");
+        game.play(0, 0, BLACK);
+        assert_eq!(text::board_to_text(&game.board, &game.image.palette).unwrap(), "#.????\n.#....\n");
+
+        // The crosses are undone after the cell
+        assert!(game.undo());
+        assert_eq!(text::board_to_text(&game.board, &game.image.palette).unwrap(), "?.????\n.#....\n");
+        assert!(game.undo());
+        assert_eq!(text::board_to_text(&game.board, &game.image.palette).unwrap(), "??????\n?#????\n");
+        assert!(game.undo());
+        assert_eq!(text::board_to_text(&game.board, &game.image.palette).unwrap(), "??????\n??????\n");
+        assert!(!game.undo());
+    }
 }
\ No newline at end of file
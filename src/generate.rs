@@ -0,0 +1,59 @@
+use image::{Rgb, RgbImage};
+
+use crate::picross_image::{Image, WHITE};
+
+const BLACK: Rgb<u8> = Rgb([0, 0, 0]);
+
+/// Minimal xorshift generator, so a seed always gives the same puzzle
+struct XorShift(u64);
+
+impl XorShift {
+    fn new(seed: u64) -> Self {
+        // The state must never be 0
+        XorShift(seed ^ 0x9E37_79B9_7F4A_7C15)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// A random number in [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Create a random black and white puzzle, where `density` is the ratio of filled cells
+pub fn random_image(width: u32, height: u32, density: f64, seed: u64) -> Image {
+    let mut rng = XorShift::new(seed);
+    let img = RgbImage::from_fn(width, height, |_, _| {
+        if rng.next_f64() < density {
+            BLACK
+        } else {
+            WHITE
+        }
+    });
+    Image::from_rgb_image(img)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_generates_the_same_puzzle_for_a_seed() {
+        let image = random_image(10, 5, 0.5, 42);
+        assert_eq!(image.width, 10);
+        assert_eq!(image.height, 5);
+        assert_eq!(image.rows.len(), 5);
+        assert_eq!(image.cols.len(), 10);
+        assert_eq!(image.to_rgb_image(), random_image(10, 5, 0.5, 42).to_rgb_image());
+        assert_ne!(image.to_rgb_image(), random_image(10, 5, 0.5, 43).to_rgb_image());
+        assert!(image.to_rgb_image().is_some());
+
+        assert!(random_image(4, 4, 0.0, 1).rows.iter().all(Vec::is_empty));
+    }
+}
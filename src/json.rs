@@ -0,0 +1,501 @@
+//! JSON puzzles, with the `serde` feature.
+//!
+//! Colors are written as `"#rrggbb"` strings, and the cells of a line or a grid are listed
+//! row by row. A puzzle looks like :
+//!
+//! ```json
+//! {
+//!   "width": 2,
+//!   "height": 2,
+//!   "palette": [{ "name": "ink", "color": "#000000" }],
+//!   "rows": [[{ "color": "ink", "count": 1 }], []],
+//!   "cols": [[{ "color": "ink", "count": 1 }], []],
+//!   "solution": ["#000000", "#ffffff", "#ffffff", "#ffffff"]
+//! }
+//! ```
+//!
+//! - `palette` is optional, it names the colors of the puzzle. A name can't be the name,
+//!   or the `"#rrggbb"` code, of another color, and a color can't have two names.
+//! - `rows` has `height` clue lists, from top to bottom, each one from left to right. The
+//!   color of a clue is a name from the palette, or a `"#rrggbb"` color.
+//! - `cols` has `width` clue lists, from left to right, each one from top to bottom.
+//! - `solution` is optional, with `width * height` colors, white being an empty cell.
+//!
+//! A [`Board`] is written as `{ "width": 2, "height": 2, "cells": [...] }`, where a cell is
+//! either the index of its color in the palette, or `"x"` for a cross. The index 0, the
+//! blank color, is a cell not played yet.
+//!
+//! A [`Clue`] alone is written as `{ "color": 1, "count": 3 }`, with the index of its color
+//! in the palette.
+//!
+//! A [`Proposition`](crate::Proposition) is written as
+//! `{ "view": "row", "index": 0, "line": [...] }`, where `view` is `"row"` or `"column"`,
+//! and `line` has a cell, or `null` when the cell is left unchanged.
+
+use std::cell::RefCell;
+use std::fmt;
+
+use image::Rgb;
+use serde::de::{self, DeserializeSeed, Deserializer, MapAccess, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::board::{Board, Pixel};
+use crate::palette::{Palette, BLANK};
+use crate::parse_error::{ParseError, ParseErrorKind};
+use crate::picross_image::{Clue, Image};
+
+/// (De)serialize a color as `"#rrggbb"`
+pub(crate) mod color {
+    use super::*;
+
+    pub fn to_hex(color: &Rgb<u8>) -> String {
+        format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+    }
+
+    pub fn from_hex(hex: &str) -> Option<Rgb<u8>> {
+        let hex = hex.strip_prefix('#')?;
+        if hex.len() != 6 {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        Some(Rgb([channel(0)?, channel(2)?, channel(4)?]))
+    }
+
+    pub fn serialize<S>(color: &Rgb<u8>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&to_hex(color))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Rgb<u8>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let hex = String::deserialize(deserializer)?;
+        from_hex(&hex).ok_or_else(|| de::Error::custom(format!("invalid color \"{}\"", hex)))
+    }
+}
+
+impl Serialize for Pixel {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Pixel::Color(c) => serializer.serialize_u8(*c),
+            Pixel::Cross => serializer.serialize_str("x"),
+        }
+    }
+}
+
+/// Read a cell : a palette index, or `"x"`
+struct PixelVisitor;
+
+impl Visitor<'_> for PixelVisitor {
+    type Value = Pixel;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a palette index or \"x\"")
+    }
+
+    fn visit_u64<E>(self, index: u64) -> Result<Pixel, E>
+    where
+        E: de::Error,
+    {
+        u8::try_from(index)
+            .map(Pixel::Color)
+            .map_err(|_| E::custom(format!("invalid cell {}", index)))
+    }
+
+    fn visit_str<E>(self, cell: &str) -> Result<Pixel, E>
+    where
+        E: de::Error,
+    {
+        match cell {
+            "x" => Ok(Pixel::Cross),
+            _ => Err(E::custom(format!("invalid cell \"{}\"", cell))),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Pixel {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(PixelVisitor)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct BoardData {
+    width: usize,
+    height: usize,
+    cells: Vec<Pixel>,
+}
+
+impl From<Board> for BoardData {
+    fn from(board: Board) -> Self {
+        let mut cells = vec![];
+        for y in 0..board.height() {
+            for x in 0..board.width() {
+                cells.push(*board.get_pixel(x, y));
+            }
+        }
+        BoardData {
+            width: board.width(),
+            height: board.height(),
+            cells,
+        }
+    }
+}
+
+impl TryFrom<BoardData> for Board {
+    type Error = ParseErrorKind;
+
+    fn try_from(data: BoardData) -> Result<Self, Self::Error> {
+        let cells = data.width.checked_mul(data.height);
+        if cells != Some(data.cells.len()) {
+            return Err(ParseErrorKind::DimensionMismatch {
+                what: "cells",
+                expected: cells.unwrap_or(usize::MAX),
+                found: data.cells.len(),
+            });
+        }
+        let mut board = Board::new(data.width, data.height);
+        for (i, pixel) in data.cells.iter().enumerate() {
+            board.set_pixel(i % data.width, i / data.width, pixel);
+        }
+        Ok(board)
+    }
+}
+
+/// A named color of the palette
+#[derive(Serialize, Deserialize)]
+struct ColorData {
+    name: String,
+    #[serde(with = "color")]
+    color: Rgb<u8>,
+}
+
+/// A clue, with the name of its color
+#[derive(Serialize, Deserialize)]
+struct ClueData {
+    color: String,
+    count: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct ImageData {
+    width: u32,
+    height: u32,
+    #[serde(default)]
+    palette: Vec<ColorData>,
+    rows: Vec<Vec<ClueData>>,
+    cols: Vec<Vec<ClueData>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    solution: Option<Vec<String>>,
+}
+
+impl From<Image> for ImageData {
+    fn from(image: Image) -> Self {
+        let solution = image
+            .to_rgb_image()
+            .map(|img| img.pixels().map(color::to_hex).collect());
+        let palette = &image.palette;
+        let clues = |lines: Vec<Vec<Clue>>| -> Vec<Vec<ClueData>> {
+            lines
+                .into_iter()
+                .map(|clues| {
+                    clues
+                        .into_iter()
+                        .map(|clue| ClueData {
+                            color: palette.name(clue.color).to_string(),
+                            count: clue.count,
+                        })
+                        .collect()
+                })
+                .collect()
+        };
+        ImageData {
+            width: image.width,
+            height: image.height,
+            palette: palette
+                .indices()
+                .map(|index| ColorData {
+                    name: palette.name(index).to_string(),
+                    color: palette.color(index),
+                })
+                .collect(),
+            rows: clues(image.rows),
+            cols: clues(image.cols),
+            solution,
+        }
+    }
+}
+
+/// The clues with the index of their colors, the colors not in the palette being added
+fn read_clues(
+    lines: Vec<Vec<ClueData>>,
+    palette: &mut Palette,
+) -> Result<Vec<Vec<Clue>>, ParseErrorKind> {
+    let mut read = vec![];
+    for clues in lines {
+        let mut line = vec![];
+        for clue in clues {
+            let color = match palette.find(&clue.color) {
+                Some(index) => index,
+                None => {
+                    let color = color::from_hex(&clue.color)
+                        .ok_or_else(|| ParseErrorKind::UnknownColor(clue.color.clone()))?;
+                    palette.add(color).ok_or(ParseErrorKind::TooManyColors)?
+                }
+            };
+            if color == BLANK || clue.count == 0 {
+                return Err(ParseErrorKind::InvalidClue { color: clue.color, count: clue.count });
+            }
+            line.push(Clue::new(color, clue.count));
+        }
+        read.push(line);
+    }
+    Ok(read)
+}
+
+/// The number of `what` isn't the expected one
+fn check_dimension(what: &'static str, expected: usize, found: usize) -> Result<(), ParseErrorKind> {
+    if expected == found {
+        Ok(())
+    } else {
+        Err(ParseErrorKind::DimensionMismatch { what, expected, found })
+    }
+}
+
+impl TryFrom<ImageData> for Image {
+    type Error = ParseErrorKind;
+
+    fn try_from(data: ImageData) -> Result<Self, Self::Error> {
+        check_dimension("rows", data.height as usize, data.rows.len())?;
+        check_dimension("columns", data.width as usize, data.cols.len())?;
+        let mut palette = Palette::new();
+        for entry in &data.palette {
+            // A clue color is looked up by name first, then as a code
+            let named = palette.find(&entry.name).map(|index| palette.color(index));
+            let code = color::from_hex(&entry.name);
+            if named.or(code).is_some_and(|color| color != entry.color) {
+                return Err(ParseErrorKind::AmbiguousColorName(entry.name.clone()));
+            }
+            if let Some(index) = palette.index(&entry.color).filter(|&i| palette.name(i) != entry.name) {
+                let other = palette.name(index).to_string();
+                return Err(ParseErrorKind::DuplicateColor { name: entry.name.clone(), other });
+            }
+            palette.add_named(entry.color, &entry.name).ok_or(ParseErrorKind::TooManyColors)?;
+        }
+        let rows = read_clues(data.rows, &mut palette)?;
+        let cols = read_clues(data.cols, &mut palette)?;
+        let solution = match data.solution {
+            None => return Ok(Image::from_clues(rows, cols, palette)),
+            Some(solution) => solution,
+        };
+        let cells = data.width as usize * data.height as usize;
+        check_dimension("cells in the solution", cells, solution.len())?;
+        let mut indices = Vec::with_capacity(cells);
+        for hex in &solution {
+            let color = color::from_hex(hex).ok_or_else(|| ParseErrorKind::InvalidColor(hex.clone()))?;
+            indices.push(palette.add(color).ok_or(ParseErrorKind::TooManyColors)?);
+        }
+        let image = Image::from_cells(data.width, data.height, indices, palette);
+        if image.rows != rows || image.cols != cols {
+            return Err(ParseErrorKind::SolutionMismatch);
+        }
+        Ok(image)
+    }
+}
+
+/// A syntax error, at its position in the text
+fn syntax_error(error: serde_json::Error) -> ParseError {
+    let message = error.to_string();
+    let position = format!(" at line {} column {}", error.line(), error.column());
+    let message = message.strip_suffix(&position).unwrap_or(&message).to_string();
+    ParseError::at(error.line(), error.column(), ParseErrorKind::Syntax(message))
+}
+
+/// Checks the puzzle while it is deserialized, so that serde_json gives the position of
+/// an error in its content. The error itself is kept aside, serde only taking a message.
+struct CheckedImage<'a>(&'a RefCell<Option<ParseErrorKind>>);
+
+impl<'de> Visitor<'de> for CheckedImage<'_> {
+    type Value = Image;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a puzzle")
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Image, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let data = ImageData::deserialize(de::value::MapAccessDeserializer::new(map))?;
+        Image::try_from(data).map_err(|kind| {
+            let error = de::Error::custom(&kind);
+            *self.0.borrow_mut() = Some(kind);
+            error
+        })
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for CheckedImage<'_> {
+    type Value = Image;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Image, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(self)
+    }
+}
+
+/// Read a puzzle. An error has its position in the text : where it is for a syntax error,
+/// and at the end of the puzzle for an error in its content.
+pub fn parse_image(json: &str) -> Result<Image, ParseError> {
+    let content_error = RefCell::new(None);
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    let image = CheckedImage(&content_error)
+        .deserialize(&mut deserializer)
+        .and_then(|image| deserializer.end().map(|_| image));
+    image.map_err(|error| match content_error.into_inner() {
+        Some(kind) => ParseError::at(error.line(), error.column(), kind),
+        None => syntax_error(error),
+    })
+}
+
+pub fn image_to_json(image: &Image) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solver::SolverBuilder;
+    use crate::text;
+    use crate::Game;
+
+    use super::*;
+
+    #[test]
+    fn it_writes_and_reads_puzzles() {
+        let image = Image::from_image("test/4x4-c.png").unwrap();
+        let json = image_to_json(&image).unwrap();
+        let read = parse_image(&json).unwrap();
+        assert_eq!(read.rows, image.rows);
+        assert_eq!(read.cols, image.cols);
+        assert_eq!(read.to_rgb_image(), image.to_rgb_image());
+
+        let json = r##"{
+            "width": 2, "height": 1,
+            "rows": [[{ "color": "#000000", "count": 1 }]],
+            "cols": [[{ "color": "#000000", "count": 1 }], []]
+        }"##;
+        let image = parse_image(json).unwrap();
+        assert!(image.to_rgb_image().is_none());
+        assert_eq!(image.rows[0][0].count, 1);
+
+        let kind = |json: &str| parse_image(json).err().map(|error| error.kind);
+        let rows = ParseErrorKind::DimensionMismatch { what: "rows", expected: 2, found: 1 };
+        assert_eq!(kind(&json.replace(r#""height": 1"#, r#""height": 2"#)), Some(rows));
+        let json = r##"{ "width": 1, "height": 1, "rows": [[]], "cols": [[]], "solution": ["#0"] }"##;
+        assert_eq!(kind(json), Some(ParseErrorKind::InvalidColor("#0".to_string())));
+
+        let error = parse_image("{\n  \"width\": 2,\n  \"height\": x\n}").err().unwrap();
+        assert_eq!(error.position, Some((3, 13)));
+        assert_eq!(error.to_string(), "Line 3, column 13: expected value");
+
+        // A content error is at the end of the puzzle
+        let error = parse_image("{\n  \"width\": 2, \"height\": 1,\n  \"rows\": [], \"cols\": []\n}").err().unwrap();
+        let rows = ParseErrorKind::DimensionMismatch { what: "rows", expected: 1, found: 0 };
+        assert_eq!(error.kind, rows);
+        assert_eq!(error.position, Some((4, 1)));
+        assert_eq!(error.to_string(), "Line 4, column 1: 1 rows expected, found 0");
+        let error = parse_image(r#"{ "width": "two" }"#).err().unwrap();
+        assert_eq!(error.position, Some((1, 16)));
+    }
+
+    #[test]
+    fn it_reads_named_colors() {
+        let json = r##"{
+            "width": 2, "height": 1,
+            "palette": [{ "name": "ink", "color": "#000080" }],
+            "rows": [[{ "color": "ink", "count": 1 }, { "color": "#ff0000", "count": 1 }]],
+            "cols": [[{ "color": "ink", "count": 1 }], [{ "color": "#ff0000", "count": 1 }]],
+            "solution": ["#000080", "#ff0000"]
+        }"##;
+        let image = parse_image(json).unwrap();
+        assert_eq!(image.palette.name(1), "ink");
+        assert_eq!(image.palette.color(1), Rgb([0, 0, 0x80]));
+        assert_eq!(image.palette.name(2), "red");
+        assert_eq!(image.rows[0], vec![Clue::new(1, 1), Clue::new(2, 1)]);
+        let read = parse_image(&image_to_json(&image).unwrap()).unwrap();
+        assert_eq!(read.palette, image.palette);
+        assert_eq!(read.rows, image.rows);
+
+        assert!(parse_image(&json.replace(r#""ink", "count""#, r#""pink", "count""#)).is_err());
+        assert!(parse_image(&json.replace(r#""ink", "count": 1 }],"#, r#""ink", "count": 0 }],"#)).is_err());
+        assert!(parse_image(&json.replace("#ff0000", "#ffffff")).is_err());
+
+        // A name must not name another color, by its name or its code
+        let palette = r##"[{ "name": "ink", "color": "#000080" }]"##;
+        let names = [
+            r##"[{ "name": "ink", "color": "#000080" }, { "name": "ink", "color": "#00ff00" }]"##,
+            r##"[{ "name": "ink", "color": "#000080" }, { "name": "white", "color": "#00ff00" }]"##,
+            r##"[{ "name": "ink", "color": "#000080" }, { "name": "#ff0000", "color": "#00ff00" }]"##,
+        ];
+        for names in names {
+            let error = parse_image(&json.replace(palette, names)).err().unwrap();
+            assert!(matches!(error.kind, ParseErrorKind::AmbiguousColorName(_)));
+        }
+        let palette_names = r##"[{ "name": "ink", "color": "#000080" }, { "name": "#ff0000", "color": "#ff0000" }]"##;
+        assert!(parse_image(&json.replace(palette, palette_names)).is_ok());
+
+        // A color must not have two names
+        let colors = [
+            r##"[{ "name": "ink", "color": "#000080" }, { "name": "navy", "color": "#000080" }]"##,
+            r##"[{ "name": "ink", "color": "#000080" }, { "name": "paper", "color": "#ffffff" }]"##,
+        ];
+        for colors in colors {
+            let error = parse_image(&json.replace(palette, colors)).err().unwrap();
+            assert!(matches!(error.kind, ParseErrorKind::DuplicateColor { .. }));
+            assert!(error.position.is_some());
+        }
+        let error = parse_image(&json.replace(palette, colors[0])).err().unwrap();
+        assert_eq!(error.kind.to_string(), "Color \"navy\" already named \"ink\"");
+    }
+
+    #[test]
+    fn it_writes_and_reads_clues() {
+        let clue = Clue::new(2, 3);
+        let json = serde_json::to_string(&clue).unwrap();
+        assert_eq!(json, r#"{"color":2,"count":3}"#);
+        assert_eq!(serde_json::from_str::<Clue>(&json).unwrap(), clue);
+        assert!(serde_json::from_str::<Clue>(r#"{"color":256,"count":3}"#).is_err());
+    }
+
+    #[test]
+    fn it_writes_and_reads_boards_and_propositions() {
+        let image = text::parse_image("#r\n").unwrap();
+        let board = text::parse_board("#.\n?r\n", &image.palette).unwrap();
+        let json = serde_json::to_string(&board).unwrap();
+        assert_eq!(json, r##"{"width":2,"height":2,"cells":[1,"x",0,2]}"##);
+        let read: Board = serde_json::from_str(&json).unwrap();
+        assert_eq!(text::board_to_text(&read, &image.palette).unwrap(), "#.\n?r\n");
+        assert!(serde_json::from_str::<Board>(r#"{"width":2,"height":2,"cells":[]}"#).is_err());
+        assert!(serde_json::from_str::<Board>(r#"{"width":1,"height":1,"cells":[256]}"#).is_err());
+        let json = format!(r#"{{"width":{},"height":2,"cells":[]}}"#, usize::MAX);
+        assert!(serde_json::from_str::<Board>(&json).is_err());
+
+        let game = Game::new("test/4x4-c.png").unwrap();
+        let proposition = SolverBuilder::new().build().solve(&game).unwrap().unwrap();
+        assert_eq!(
+            serde_json::to_string(&proposition).unwrap(),
+            r##"{"view":"row","line":[1,1,1,1],"index":0}"##
+        );
+    }
+}
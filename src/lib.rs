@@ -1,9 +1,24 @@
 mod game;
 mod board;
+mod palette;
+mod parse_error;
 mod picross_image;
 mod solver;
+pub mod animation;
+pub mod batch;
+pub mod generate;
+#[cfg(feature = "serde")]
+pub mod json;
+pub mod render;
+pub mod text;
+pub mod uniqueness;
 
-pub use game::Game;
-pub use board::Board;
-pub use picross_image::Image;
-pub use solver::SolverBuilder;
\ No newline at end of file
+pub use game::{blocks, check_clues, Assists, ClueError, Event, Game, GameError, Hint, Mode};
+pub use board::{Board, Pixel};
+pub use palette::{Palette, BLANK};
+pub use parse_error::{ParseError, ParseErrorKind};
+pub use picross_image::{Clue, Image, ImageError, WHITE};
+pub use solver::{
+    deduce, ColorPlacements, Contradiction, FullLine, GameLine, GameView, Placements, Proposition,
+    Reason, Solver, SolverAlgo, SolverBuilder,
+};
\ No newline at end of file
@@ -1,43 +1,344 @@
-use std::env;
+use std::error::Error;
+use std::fs;
+use std::io::{self, BufRead, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-//mod window;
+use clap::{Parser, Subcommand, ValueEnum};
 
-use picross_rs::Game;
-use picross_rs::SolverBuilder;
+mod tui;
+mod window;
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    match args.get(1) {
-        None => {
-            println!("usage : picross <filename>")
-        }
-        Some(filename) => {
-            match Game::new(filename) {
-                Err(e) => eprintln!("Error initializing game \"{}\"\n{}", filename, e),
-                Ok(mut game) => {
-                    //window::show(game);
-                    if play(&mut game) {
-                        println!("YOU WIN")
-                    } 
-                    else {
-                        println!("NOT FINISHED")
-                    }
+use picross_rs::{animation, batch, generate, render, uniqueness};
+use picross_rs::{check_clues, Assists, Board, Game, Image, ImageError, Mode, SolverBuilder};
+
+/// Exit code when the puzzle isn't solved, or isn't unique
+const EXIT_FAILURE: u8 = 1;
+/// Exit code when a file can't be read or written
+const EXIT_ERROR: u8 = 3;
+
+#[derive(Parser)]
+#[command(version, about = "Solve, play and create picross puzzles")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Solve a puzzle with the solver
+    Solve {
+        file: PathBuf,
+        /// Wait for <Enter> after each step
+        #[arg(long, conflicts_with = "quiet")]
+        step: bool,
+        /// Print the board after each step
+        #[arg(short, long, conflicts_with = "quiet")]
+        verbose: bool,
+        /// Don't print anything, only set the exit code
+        #[arg(short, long)]
+        quiet: bool,
+        /// Solve all the rows, then all the columns, at a time: faster on large puzzles, in
+        /// parallel with the `parallel` feature. The steps are still shown one by one.
+        #[arg(long, conflicts_with = "step")]
+        sweep: bool,
+        /// Save the solving steps as an animated GIF
+        #[arg(long)]
+        gif: Option<PathBuf>,
+        /// Delay between the GIF frames, in milliseconds
+        #[arg(long, default_value_t = 500, requires = "gif")]
+        delay: u64,
+    },
+    /// Play a puzzle in the terminal, or in a window
+    Play {
+        file: PathBuf,
+        /// Open a window instead of playing in the terminal
+        #[arg(long)]
+        gui: bool,
+        /// Cross the remaining cells of a line once its clues are matched
+        #[arg(long)]
+        auto_cross: bool,
+        /// Check each fill against the solution, and lose after this number of mistakes
+        #[arg(long)]
+        max_mistakes: Option<u32>,
+        /// Time added for each mistake, in seconds
+        #[arg(long, default_value_t = 60, requires = "max_mistakes")]
+        penalty: u64,
+    },
+    /// Convert a puzzle to another format, deduced from the output extension
+    Convert { input: PathBuf, output: PathBuf },
+    /// Generate a random black and white puzzle
+    Generate {
+        output: PathBuf,
+        #[arg(long, default_value_t = 10)]
+        width: u32,
+        #[arg(long, default_value_t = 10)]
+        height: u32,
+        /// Ratio of filled cells
+        #[arg(long, default_value_t = 0.5)]
+        density: f64,
+        #[arg(long)]
+        seed: Option<u64>,
+        /// Flip the fewest pixels so the puzzle is line solvable, with a unique solution
+        #[arg(long)]
+        unique: bool,
+    },
+    /// Check that a puzzle has a unique solution, and tell if it is line solvable
+    CheckUnique {
+        file: PathBuf,
+        /// Flip the fewest pixels to make the puzzle line solvable, and save it to this file
+        #[arg(long)]
+        fix: Option<PathBuf>,
+    },
+    /// Render a puzzle with its clues to a PNG or SVG file
+    Render {
+        file: PathBuf,
+        output: PathBuf,
+        /// Render the solution instead of an empty grid
+        #[arg(long)]
+        solution: bool,
+    },
+    /// Solve every puzzle of a directory, and report the solver statistics
+    Batch {
+        dir: PathBuf,
+        #[arg(long, value_enum, default_value_t = ReportFormat::Json)]
+        format: ReportFormat,
+        /// Write the report to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ReportFormat {
+    Json,
+    Csv,
+}
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match cli.command {
+        Command::Solve { file, step, verbose, quiet, sweep, gif, delay } => {
+            let output = if quiet {
+                Output::Quiet
+            } else if step {
+                Output::Step
+            } else if verbose {
+                Output::Verbose
+            } else {
+                Output::Normal
+            };
+            let gif = gif.map(|gif| (gif, Duration::from_millis(delay)));
+            solve(&file, output, sweep, gif)
+        }
+        Command::Play { file, gui, auto_cross, max_mistakes, penalty } => {
+            let mode = match max_mistakes {
+                Some(max_mistakes) => Mode::Penalty { max_mistakes, penalty: Duration::from_secs(penalty) },
+                None => Mode::Free,
+            };
+            play(&file, gui, Assists { auto_cross }, mode)
+        }
+        Command::Convert { input, output } => convert(&input, &output),
+        Command::Generate { output, width, height, density, seed, unique } => {
+            generate(&output, width, height, density, seed, unique)
+        }
+        Command::CheckUnique { file, fix } => check_unique(&file, fix.as_deref()),
+        Command::Render { file, output, solution } => render(&file, &output, solution),
+        Command::Batch { dir, format, output } => batch(&dir, format, output.as_deref()),
+    };
+    match result {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("Error : {}", e);
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
+}
+
+fn exit_code(success: bool) -> ExitCode {
+    if success {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(EXIT_FAILURE)
+    }
+}
+
+/// The puzzle of the file, once its clues are checked
+fn load(filename: &Path) -> Result<Image> {
+    let image = Image::open(filename).map_err(|e| -> Box<dyn Error> {
+        match e {
+            // The parse errors already name the file, with the position of the error
+            ImageError::ParseError(e) => e.into(),
+            e => format!("{}: {}", filename.display(), e).into(),
+        }
+    })?;
+    check_clues(&image).map_err(|e| format!("{}: {}", filename.display(), e))?;
+    Ok(image)
+}
+
+fn new_game(filename: &Path) -> Result<Game> {
+    Ok(Game::with_image(load(filename)?))
+}
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum Output {
+    Quiet,
+    Normal,
+    Verbose,
+    Step,
+}
+
+fn solve(filename: &Path, output: Output, sweep: bool, gif: Option<(PathBuf, Duration)>) -> Result<ExitCode> {
+    let mut game = new_game(filename)?;
+    let solver = SolverBuilder::new().build();
+    let image = game.image.clone();
+    let mut recorder = animation::Recorder::new(&image);
+    recorder.record(&game.board);
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    let ansi = io::stdout().is_terminal();
+    // Record and print the board after each merged proposition, false to stop
+    let mut show = |game: &Game| -> bool {
+        if gif.is_some() {
+            recorder.record(&game.board);
+        }
+        if output == Output::Verbose || output == Output::Step {
+            println!("BOARD");
+            print!("{}", render::to_terminal(&game.image, &game.board, ansi));
+        }
+        output != Output::Step || lines.next().is_some()
+    };
+    let mut contradiction = None;
+    if sweep {
+        // A whole sweep at a time, in parallel with the `parallel` feature
+        contradiction = solver.run(&mut game, |game, _, _| {
+            show(game);
+        }).err();
+    } else {
+        while !game.is_finished() {
+            match solver.step(&game) {
+                Ok(Some((_, proposition))) => game.apply(&proposition),
+                Ok(None) => break,
+                Err(e) => {
+                    contradiction = Some(e);
+                    break;
                 }
             }
-        },
+            if !show(&game) {
+                break;
+            }
+        }
+    }
+    let finished = game.is_finished();
+    if let Some((gif, delay)) = gif {
+        recorder.save_gif(gif, delay)?;
+    }
+    if output != Output::Quiet {
+        if output == Output::Normal {
+            print!("{}", render::to_terminal(&game.image, &game.board, ansi));
+        }
+        if let Some(contradiction) = contradiction {
+            println!("{}", contradiction);
+        }
+        println!("{}", if finished { "YOU WIN" } else { "NOT FINISHED" });
     }
+    Ok(exit_code(finished))
 }
 
-fn play(game: &mut Game) -> bool {
-    let solver = SolverBuilder::new().build();
-    while !game.is_finished() {
-        if let Some(proposition) = solver.solve(game) {
-            proposition.merge(&mut game.board);
-        } else {
-            return false;
+fn play(filename: &Path, gui: bool, assists: Assists, mode: Mode) -> Result<ExitCode> {
+    let mut game = new_game(filename)?;
+    game.assists = assists;
+    game.set_mode(mode)?;
+    if gui {
+        game = window::show(game)?;
+    } else {
+        tui::play(&mut game)?;
+    }
+    Ok(exit_code(game.is_finished()))
+}
+
+fn convert(input: &Path, output: &Path) -> Result<ExitCode> {
+    load(input)?.save(output)?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn generate(output: &Path, width: u32, height: u32, density: f64, seed: Option<u64>, unique: bool) -> Result<ExitCode> {
+    let seed = match seed {
+        Some(seed) => seed,
+        None => SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos() as u64,
+    };
+    let mut image = generate::random_image(width, height, density, seed);
+    if unique {
+        match uniqueness::suggest_flips(&image) {
+            Some(flips) => image = uniqueness::apply_flips(&image, &flips),
+            None => {
+                eprintln!("Unable to make the puzzle line solvable");
+                return Ok(exit_code(false));
+            }
         }
-        println!("BOARD");
-        println!("{}", game.board);
     }
-    true
+    image.save(output)?;
+    println!("seed {}", seed);
+    Ok(ExitCode::SUCCESS)
+}
+
+fn check_unique(filename: &Path, fix: Option<&Path>) -> Result<ExitCode> {
+    let image = load(filename)?;
+    let ambiguous = uniqueness::ambiguous_cells(&image).len();
+    let unique = ambiguous == 0 || uniqueness::has_unique_solution(&image);
+    match (unique, ambiguous) {
+        (_, 0) => println!("Unique solution, line solvable"),
+        (true, n) => println!("Unique solution, but {} cells can't be deduced line by line", n),
+        (false, n) => println!("Several solutions, {} cells can't be deduced line by line", n),
+    }
+    let output = match fix {
+        None => return Ok(exit_code(unique)),
+        Some(output) => output,
+    };
+    match uniqueness::suggest_flips(&image) {
+        None => {
+            eprintln!("Unable to make the puzzle line solvable");
+            Ok(exit_code(false))
+        }
+        Some(flips) => {
+            for flip in &flips {
+                println!("flip ({}, {}) : {:?} -> {:?}", flip.x, flip.y, flip.from.0, flip.to.0);
+            }
+            uniqueness::apply_flips(&image, &flips).save(output)?;
+            Ok(ExitCode::SUCCESS)
+        }
+    }
+}
+
+fn render(filename: &Path, output: &Path, solution: bool) -> Result<ExitCode> {
+    let image = load(filename)?;
+    let board = if solution {
+        Board::from(&image)
+    } else {
+        Board::new(image.width as usize, image.height as usize)
+    };
+    let is_svg = output.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("svg"));
+    if is_svg {
+        fs::write(output, render::to_svg(&image, &board))?;
+    } else {
+        render::to_png(&image, &board).save(output)?;
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn batch(dir: &Path, format: ReportFormat, output: Option<&Path>) -> Result<ExitCode> {
+    let reports = batch::solve_dir(dir)?;
+    let report = match format {
+        ReportFormat::Json => batch::to_json(&reports),
+        ReportFormat::Csv => batch::to_csv(&reports),
+    };
+    match output {
+        Some(output) => fs::write(output, report)?,
+        None => print!("{}", report),
+    }
+    let solved = reports.iter().all(|report| report.status == batch::Status::Solved);
+    Ok(exit_code(solved))
 }
@@ -0,0 +1,155 @@
+use image::Rgb;
+
+use crate::picross_image::WHITE;
+
+/// Index of the blank color, white, in every palette : an empty cell of a solution, or a
+/// cell not played yet on a board
+pub const BLANK: u8 = 0;
+
+/// The colors of a puzzle, with their names. Cells and clues hold the index of their
+/// color, the blank color always being the first one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Palette {
+    colors: Vec<(Rgb<u8>, String)>,
+}
+
+/// Names of the usual colors, the others are named after their hex code
+const NAMES: [(Rgb<u8>, &str); 10] = [
+    (WHITE, "white"),
+    (Rgb([0x00, 0x00, 0x00]), "black"),
+    (Rgb([0xFF, 0x00, 0x00]), "red"),
+    (Rgb([0x00, 0x80, 0x00]), "green"),
+    (Rgb([0x00, 0x00, 0xFF]), "blue"),
+    (Rgb([0xFF, 0xFF, 0x00]), "yellow"),
+    (Rgb([0xFF, 0xA5, 0x00]), "orange"),
+    (Rgb([0x80, 0x00, 0x80]), "purple"),
+    (Rgb([0x00, 0xFF, 0xFF]), "cyan"),
+    (Rgb([0xFF, 0x00, 0xFF]), "magenta"),
+];
+
+/// The default name of a color
+fn default_name(color: &Rgb<u8>) -> String {
+    match NAMES.iter().find(|(named, _)| named == color) {
+        Some((_, name)) => name.to_string(),
+        None => format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2]),
+    }
+}
+
+impl Palette {
+    /// A palette with only the blank color
+    pub fn new() -> Self {
+        Palette {
+            colors: vec![(WHITE, default_name(&WHITE))],
+        }
+    }
+
+    /// Number of colors, with the blank one
+    pub fn len(&self) -> usize {
+        self.colors.len()
+    }
+
+    /// The palette has only the blank color
+    pub fn is_empty(&self) -> bool {
+        self.colors.len() == 1
+    }
+
+    /// Add a color with its default name, and return its index, or `None` if the palette
+    /// is full. A color already in the palette keeps its index.
+    pub fn add(&mut self, color: Rgb<u8>) -> Option<u8> {
+        match self.index(&color) {
+            Some(index) => Some(index),
+            None => self.add_named(color, &default_name(&color)),
+        }
+    }
+
+    /// Add a color with a name, and return its index, or `None` if the palette is full.
+    /// A color already in the palette keeps its index and its name.
+    pub fn add_named(&mut self, color: Rgb<u8>, name: &str) -> Option<u8> {
+        if let Some(index) = self.index(&color) {
+            return Some(index);
+        }
+        let index = u8::try_from(self.colors.len()).ok()?;
+        self.colors.push((color, name.to_string()));
+        Some(index)
+    }
+
+    /// The index of a color
+    pub fn index(&self, color: &Rgb<u8>) -> Option<u8> {
+        self.colors.iter().position(|(c, _)| c == color).map(|index| index as u8)
+    }
+
+    /// The index of the color with this name
+    pub fn find(&self, name: &str) -> Option<u8> {
+        self.colors.iter().position(|(_, n)| n == name).map(|index| index as u8)
+    }
+
+    /// The color at `index`.
+    ///
+    /// Panics if the index isn't in the palette.
+    pub fn color(&self, index: u8) -> Rgb<u8> {
+        self.colors[index as usize].0
+    }
+
+    /// The name of the color at `index`.
+    ///
+    /// Panics if the index isn't in the palette.
+    pub fn name(&self, index: u8) -> &str {
+        &self.colors[index as usize].1
+    }
+
+    /// Change the color at `index`, to draw the puzzle with another theme.
+    ///
+    /// Returns false, leaving the palette unchanged, if another index already has `color` :
+    /// the colors must stay distinct to be found by [`Palette::index`].
+    pub fn set_color(&mut self, index: u8, color: Rgb<u8>) -> bool {
+        if self.index(&color).is_some_and(|other| other != index) {
+            return false;
+        }
+        self.colors[index as usize].0 = color;
+        true
+    }
+
+    /// The indices of the colors, without the blank one
+    pub fn indices(&self) -> impl Iterator<Item = u8> {
+        1..self.colors.len() as u8
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_indexes_the_colors() {
+        let mut palette = Palette::new();
+        assert!(palette.is_empty());
+        assert_eq!(palette.index(&WHITE), Some(BLANK));
+        assert_eq!(palette.add(Rgb([0xFF, 0, 0])), Some(1));
+        assert_eq!(palette.add(Rgb([0x12, 0x34, 0x56])), Some(2));
+        assert_eq!(palette.add_named(Rgb([0xFF, 0, 0]), "rouge"), Some(1));
+        assert_eq!(palette.add_named(Rgb([0, 0, 0]), "ink"), Some(3));
+        assert_eq!(palette.len(), 4);
+        assert_eq!(palette.name(1), "red");
+        assert_eq!(palette.name(2), "#123456");
+        assert_eq!(palette.find("ink"), Some(3));
+        assert_eq!(palette.indices().collect::<Vec<_>>(), vec![1, 2, 3]);
+
+        assert!(palette.set_color(1, Rgb([0xC0, 0, 0])));
+        assert_eq!(palette.color(1), Rgb([0xC0, 0, 0]));
+        assert_eq!(palette.index(&Rgb([0xFF, 0, 0])), None);
+        assert!(!palette.set_color(1, Rgb([0, 0, 0])));
+        assert_eq!(palette.color(1), Rgb([0xC0, 0, 0]));
+
+        for blue in 4..=u8::MAX {
+            assert_eq!(palette.add(Rgb([0, 0, blue])), Some(blue));
+        }
+        assert_eq!(palette.add(Rgb([0, 1, 0])), None);
+        assert_eq!(palette.add(Rgb([0, 0, 4])), Some(4));
+    }
+}
@@ -0,0 +1,128 @@
+use std::error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// What is wrong in a puzzle file, whatever its format
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// The file isn't well formed for its format
+    Syntax(String),
+    /// The grid doesn't contain any cell
+    Empty,
+    /// A character that isn't a cell
+    UnknownSymbol(char),
+    /// A color that can't be read
+    InvalidColor(String),
+    /// A color that isn't in the palette of the puzzle
+    UnknownColor(String),
+    /// A name of the palette which is already the name, or the `#rrggbb` code, of another
+    /// color
+    AmbiguousColorName(String),
+    /// A color of the palette which already has another name
+    DuplicateColor { name: String, other: String },
+    /// An unknown cell in a solution
+    UnknownCell,
+    /// A clue with no cell, or of the blank color
+    InvalidClue { color: String, count: u32 },
+    /// The puzzle has more colors than a palette can hold
+    TooManyColors,
+    /// The number of rows, columns or cells doesn't match the size of the puzzle
+    DimensionMismatch { what: &'static str, expected: usize, found: usize },
+    /// The solution doesn't give the clues of the puzzle
+    SolutionMismatch,
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseErrorKind::Syntax(message) => write!(f, "{}", message),
+            ParseErrorKind::Empty => write!(f, "Empty grid"),
+            ParseErrorKind::UnknownSymbol(c) => write!(f, "Unknown symbol '{}'", c),
+            ParseErrorKind::InvalidColor(color) => write!(f, "Invalid color \"{}\"", color),
+            ParseErrorKind::UnknownColor(color) => {
+                write!(f, "Color \"{}\" not in the puzzle", color)
+            }
+            ParseErrorKind::AmbiguousColorName(name) => {
+                write!(f, "Color name \"{}\" already names another color", name)
+            }
+            ParseErrorKind::DuplicateColor { name, other } => {
+                write!(f, "Color \"{}\" already named \"{}\"", name, other)
+            }
+            ParseErrorKind::UnknownCell => write!(f, "Unknown cell in a solution"),
+            ParseErrorKind::InvalidClue { color, count } => {
+                write!(f, "Invalid clue of {} \"{}\" cells", count, color)
+            }
+            ParseErrorKind::TooManyColors => write!(f, "More than {} colors", u8::MAX),
+            ParseErrorKind::DimensionMismatch { what, expected, found } => {
+                write!(f, "{} {} expected, found {}", expected, what, found)
+            }
+            ParseErrorKind::SolutionMismatch => write!(f, "The solution doesn't match the clues"),
+        }
+    }
+}
+
+/// A puzzle file that can't be read, with the position of the error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// The file, unknown when parsing a string
+    pub file: Option<PathBuf>,
+    /// The line and the column of the error, from 1, when the format gives them
+    pub position: Option<(usize, usize)>,
+    pub kind: ParseErrorKind,
+}
+
+impl ParseError {
+    pub(crate) fn new(kind: ParseErrorKind) -> Self {
+        ParseError { file: None, position: None, kind }
+    }
+
+    pub(crate) fn at(line: usize, column: usize, kind: ParseErrorKind) -> Self {
+        ParseError { file: None, position: Some((line, column)), kind }
+    }
+
+    /// The same error, in the file
+    pub fn in_file<P>(self, file: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        ParseError { file: Some(file.as_ref().to_path_buf()), ..self }
+    }
+}
+
+impl From<ParseErrorKind> for ParseError {
+    fn from(kind: ParseErrorKind) -> Self {
+        ParseError::new(kind)
+    }
+}
+
+/// Written like a compiler message : `file:line:column: error`
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.file, self.position) {
+            (Some(file), Some((line, column))) => {
+                write!(f, "{}:{}:{}: ", file.display(), line, column)?
+            }
+            (Some(file), None) => write!(f, "{}: ", file.display())?,
+            (None, Some((line, column))) => write!(f, "Line {}, column {}: ", line, column)?,
+            (None, None) => {}
+        }
+        write!(f, "{}", self.kind)
+    }
+}
+
+impl error::Error for ParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_writes_the_position_of_the_error() {
+        let error = ParseError::at(2, 3, ParseErrorKind::UnknownSymbol('z'));
+        assert_eq!(error.to_string(), "Line 2, column 3: Unknown symbol 'z'");
+        assert_eq!(error.in_file("puzzle.txt").to_string(), "puzzle.txt:2:3: Unknown symbol 'z'");
+
+        let error = ParseError::new(ParseErrorKind::SolutionMismatch).in_file("puzzle.json");
+        assert_eq!(error.to_string(), "puzzle.json: The solution doesn't match the clues");
+    }
+}
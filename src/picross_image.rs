@@ -1,69 +1,114 @@
 use std::error;
 use std::fmt;
 use std::fmt::Display;
+use std::fs;
 use std::path::Path;
 
-use image::{Rgb, io::Reader as ImageReader, DynamicImage, RgbImage, GenericImageView};
+use image::{Rgb, io::Reader as ImageReader, ColorType, DynamicImage, RgbImage};
 
 use crate::board::Board;
 use crate::board::Pixel;
-
-#[derive(Debug)]
+use crate::game::blocks;
+use crate::palette::{Palette, BLANK};
+use crate::parse_error::ParseError;
+use crate::text;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "crate::json::ImageData", into = "crate::json::ImageData")
+)]
 pub struct Image {
     pub width: u32,
     pub height: u32,
     pub rows: Vec<Vec<Clue>>,
     pub cols: Vec<Vec<Clue>>,
-    img: RgbImage
+    /// The colors of the clues and of the cells
+    pub palette: Palette,
+    /// The solution, as the palette index of each cell row by row, unknown when the puzzle
+    /// is only made of clues
+    cells: Option<Vec<u8>>
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Clue {
-    pub color: image::Rgb<u8>,
+    /// Index of the color in the palette of the puzzle
+    pub color: u8,
     pub count: u32,
 }
 
 impl Display for Clue {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Clue ({:?}, {})", self.color, self.count)
+        write!(f, "Clue ({}, {})", self.color, self.count)
     }
 }
 
 impl Clue {
-    fn new(color: Rgb<u8>, count: u32) -> Self {
+    pub(crate) fn new(color: u8, count: u32) -> Self {
         Clue { color, count }
     }
 }
 
 
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum ImageError {
     IoError(std::io::Error),
     ImageError(image::ImageError),
-    UnsupportedFormatError(DynamicImage),
+    /// The color type of a picture that isn't in RGB
+    UnsupportedFormatError(ColorType),
+    /// The picture has more colors than a palette can hold
+    TooManyColorsError,
+    /// The puzzle has more colors than letters to write them in a text grid
+    TooManyLettersError,
+    /// A puzzle file that can't be read
+    ParseError(ParseError),
+    /// The puzzle has no solution picture to save
+    NoSolutionError,
+    /// The puzzle can't be written in JSON
+    #[cfg(feature = "serde")]
+    JsonError(serde_json::Error),
 }
 
 impl fmt::Display for ImageError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match &*self {
-            ImageError::IoError(e) => write!(f, "{}", e.to_string()),
-            ImageError::ImageError(e) => write!(f, "{}", e.to_string()),
-            ImageError::UnsupportedFormatError(img) => {
-                write!(f, "Unsupported image format : {:?}", img)
+        match self {
+            ImageError::IoError(e) => write!(f, "{}", e),
+            ImageError::ImageError(e) => write!(f, "{}", e),
+            ImageError::UnsupportedFormatError(color) => {
+                write!(f, "Unsupported image format : {:?}, the picture must be in RGB", color)
+            }
+            ImageError::TooManyColorsError => {
+                write!(f, "The picture has more than {} colors", u8::MAX)
             }
+            ImageError::TooManyLettersError => {
+                write!(f, "The puzzle has more colors than letters for a text grid")
+            }
+            ImageError::ParseError(e) => write!(f, "{}", e),
+            ImageError::NoSolutionError => write!(f, "The puzzle has no solution"),
+            #[cfg(feature = "serde")]
+            ImageError::JsonError(e) => write!(f, "{}", e),
         }
     }
 }
 
 impl error::Error for ImageError {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
-        match &*self {
+        match self {
             ImageError::IoError(ref e) => Some(e),
             // The cause is the underlying implementation error type. Is implicitly
             // cast to the trait object `&error::Error`. This works because the
             // underlying type already implements the `Error` trait.
             ImageError::ImageError(ref e) => Some(e),
             ImageError::UnsupportedFormatError(_) => None,
+            ImageError::TooManyColorsError => None,
+            ImageError::TooManyLettersError => None,
+            ImageError::ParseError(ref e) => Some(e),
+            ImageError::NoSolutionError => None,
+            #[cfg(feature = "serde")]
+            ImageError::JsonError(ref e) => Some(e),
         }
     }
 }
@@ -80,39 +125,49 @@ impl From<image::ImageError> for ImageError {
     }
 }
 
+impl From<ParseError> for ImageError {
+    fn from(err: ParseError) -> ImageError {
+        ImageError::ParseError(err)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<serde_json::Error> for ImageError {
+    fn from(err: serde_json::Error) -> ImageError {
+        ImageError::JsonError(err)
+    }
+}
+
 /// Result type for the picross game
 type Result<T> = std::result::Result<T, ImageError>;
 
 pub const WHITE: Rgb<u8> = Rgb([0xFF, 0xFF, 0xFF]);
 
-fn is_white(p: &Rgb<u8>) -> bool {
-    return p[0] == WHITE[0] && p[1] == WHITE[1] && p[2] == WHITE[2];
-}
-
 struct Counter<'a> {
-    image: &'a RgbImage,
+    cells: &'a [u8],
+    width: u32,
     counter: u32,
-    current_color: Rgb<u8>,
+    current_color: u8,
 }
 
-///
 impl<'a> Counter<'a> {
-    fn new(image: &'a RgbImage) -> Self {
+    fn new(cells: &'a [u8], width: u32) -> Self {
         Counter {
-            image,
+            cells,
+            width,
             counter: 0,
-            current_color: WHITE,
+            current_color: BLANK,
         }
     }
 
     fn reset(&mut self) {
         self.counter = 0;
-        self.current_color = WHITE;
+        self.current_color = BLANK;
     }
 
     fn next(&mut self, x: u32, y: u32) -> Option<Clue> {
-        let pix = self.image.get_pixel(x, y);
-        if is_white(&pix) {
+        let pix = &self.cells[(x + y * self.width) as usize];
+        if *pix == BLANK {
             if self.counter > 0 {
                 let clue = self.clue();
                 // println!("Counter::next({},{}) return {}", x, y, clue);
@@ -123,6 +178,12 @@ impl<'a> Counter<'a> {
             if pix.eq(&self.current_color) {
                 self.counter += 1;
                 // println!("Counter::next({},{}) increment  {:?} : {}", x, y, self.current_color, self.counter);
+            } else if self.counter > 0 {
+                // A block of another color starts right after this one
+                let clue = self.clue();
+                self.current_color = *pix;
+                self.counter = 1;
+                return Some(clue);
             } else {
                 self.current_color = *pix;
                 self.counter = 1;
@@ -147,22 +208,74 @@ impl<'a> Counter<'a> {
     }
 }
 
-///
+fn has_extension<P>(filename: P, extension: &str) -> bool
+where
+    P: AsRef<Path>,
+{
+    filename.as_ref().extension().is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+}
+
 impl Image {
+    /// Load a puzzle, from a text grid if the extension is `.txt`, from JSON if it is
+    /// `.json` (with the `serde` feature), else from a picture
+    pub fn open<P>(filename: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        if has_extension(&filename, "txt") {
+            let image = text::parse_image(&fs::read_to_string(&filename)?);
+            return Ok(image.map_err(|e| e.in_file(&filename))?);
+        }
+        #[cfg(feature = "serde")]
+        if has_extension(&filename, "json") {
+            let image = crate::json::parse_image(&fs::read_to_string(&filename)?);
+            return Ok(image.map_err(|e| e.in_file(&filename))?);
+        }
+        Image::from_image(filename)
+    }
+
     pub fn from_image<P>(filename: P) -> Result<Self>
     where
         P: AsRef<Path>,
     {
-        let img = ImageReader::open(filename)?.decode()?;
-        let imgbuffer = match img.as_rgb8() {
-            Some(i) => i,
-            None => return Err(ImageError::UnsupportedFormatError(img))
-        };
+        Image::from_dynamic_image(ImageReader::open(filename)?.decode()?)
+    }
 
-        let width = img.width();
-        let height = img.height();
+    /// Create the puzzle from a decoded picture, which must be in RGB with at most 255
+    /// colors besides white
+    pub fn from_dynamic_image(img: DynamicImage) -> Result<Self> {
+        match img {
+            DynamicImage::ImageRgb8(imgbuffer) => {
+                let mut palette = Palette::new();
+                for pixel in imgbuffer.pixels() {
+                    palette.add(*pixel).ok_or(ImageError::TooManyColorsError)?;
+                }
+                Ok(Image::with_palette(imgbuffer, palette))
+            }
+            _ => Err(ImageError::UnsupportedFormatError(img.color()))
+        }
+    }
 
-        let mut counter = Counter::new(imgbuffer);
+    /// Create the puzzle from an in memory picture, computing the rows and cols clues.
+    ///
+    /// Panics if the picture has more than 255 colors besides white.
+    pub fn from_rgb_image(imgbuffer: RgbImage) -> Self {
+        Image::with_palette(imgbuffer, Palette::new())
+    }
+
+    /// Create the puzzle from an in memory picture, with the colors of the palette first.
+    /// The other colors of the picture are added in order of appearance, row by row.
+    ///
+    /// Panics if the palette can't hold all the colors of the picture.
+    pub fn with_palette(imgbuffer: RgbImage, mut palette: Palette) -> Self {
+        let cells = imgbuffer.pixels().map(|pixel| palette.add(*pixel).expect("too many colors")).collect();
+        Image::from_cells(imgbuffer.width(), imgbuffer.height(), cells, palette)
+    }
+
+    /// Create the puzzle from its solution, the palette index of each cell row by row,
+    /// computing the rows and cols clues
+    pub(crate) fn from_cells(width: u32, height: u32, cells: Vec<u8>, palette: Palette) -> Self {
+        let mut counter = Counter::new(&cells, width);
 
         // Get the rows
         let mut rows = vec![];
@@ -173,7 +286,6 @@ impl Image {
                 if let Some(clue) = counter.next(x, y) {
                     // println!("===================================== pushing {:?}", clue);
                     v.push(clue);
-                    counter.reset();
                 }
             }
             if let Some(clue) = counter.end() {
@@ -192,7 +304,6 @@ impl Image {
                 if let Some(clue) = counter.next(x, y) {
                     // println!("===================================== pushing {:?}", clue);
                     v.push(clue);
-                    counter.reset();
                 }
             }
             if let Some(clue) = counter.end() {
@@ -202,36 +313,129 @@ impl Image {
             cols.push(v);
         }
 
-        Ok(Image {
+        Image {
             width,
             height,
             rows,
             cols,
-            img: imgbuffer.clone()
-        })
+            palette,
+            cells: Some(cells)
+        }
+    }
+
+    /// Create a puzzle from its clues only, without knowing the solution. The colors of
+    /// the clues are indices in the palette.
+    pub fn from_clues(rows: Vec<Vec<Clue>>, cols: Vec<Vec<Clue>>, palette: Palette) -> Self {
+        Image {
+            width: cols.len() as u32,
+            height: rows.len() as u32,
+            rows,
+            cols,
+            palette,
+            cells: None
+        }
+    }
+
+    /// The palette indices of the colors used by the clues, in order of appearance
+    pub fn colors(&self) -> Vec<u8> {
+        let mut colors = vec![];
+        for clue in self.rows.iter().chain(self.cols.iter()).flatten() {
+            if !colors.contains(&clue.color) {
+                colors.push(clue.color);
+            }
+        }
+        colors
+    }
+
+    /// The picture of the solution, drawn with the colors of the palette, if known
+    pub fn to_rgb_image(&self) -> Option<RgbImage> {
+        let cells = self.cells.as_ref()?;
+        Some(RgbImage::from_fn(self.width, self.height, |x, y| {
+            self.palette.color(cells[(x + y * self.width) as usize])
+        }))
+    }
+
+    /// The solution of the puzzle is known
+    pub fn has_solution(&self) -> bool {
+        self.cells.is_some()
+    }
+
+    /// The palette index of the color of a cell in the solution, if known
+    pub fn solution(&self, x: u32, y: u32) -> Option<u8> {
+        Some(self.cells.as_ref()?[(x + y * self.width) as usize])
+    }
+
+    /// Change a color of the palette, the cells of the solution keeping their index.
+    ///
+    /// Returns false, leaving the palette unchanged, if another color of the palette is
+    /// already `color`.
+    pub fn set_color(&mut self, index: u8, color: Rgb<u8>) -> bool {
+        self.palette.set_color(index, color)
+    }
+
+    /// Save the puzzle, the format is deduced from the file extension like in [`Image::open`].
+    ///
+    /// Only JSON puzzles can be saved without a solution.
+    pub fn save<P>(&self, filename: P) -> Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        #[cfg(feature = "serde")]
+        if has_extension(&filename, "json") {
+            fs::write(filename, crate::json::image_to_json(self)?)?;
+            return Ok(());
+        }
+        let img = self.to_rgb_image().ok_or(ImageError::NoSolutionError)?;
+        if has_extension(&filename, "txt") {
+            fs::write(filename, text::image_to_text(&img)?)?;
+        } else {
+            img.save(filename)?;
+        }
+        Ok(())
     }
 }
 
+/// The solved board of the puzzle, or an empty board if the solution is unknown
+impl From<&Image> for Board {
+    fn from(image: &Image) -> Self {
+        let mut board = Board::new(image.width as usize, image.height as usize);
+        for y in 0..image.height {
+            for x in 0..image.width {
+                let pixel = match image.solution(x, y) {
+                    None => return board,
+                    Some(BLANK) => Pixel::Cross,
+                    Some(color) => Pixel::Color(color),
+                };
+                board.set_pixel(x as usize, y as usize, &pixel);
+            }
+        }
+        board
+    }
+}
+
+/// The board is the solution of the puzzle. Without a solution picture, the filled cells
+/// must give the same clues as the puzzle.
 impl PartialEq<Image> for Board {
     fn eq(&self, other: &Image) -> bool {
-        for y in 0..self.width(){
-            for x in 0..self.height() {
-                let img_color = other.img.get_pixel(x as u32, y as u32);
-                match self.get_pixel(x, y) {
-                    Pixel::Color(board_color) => {
-                        if !img_color.eq(board_color) {
-                            return false;
-                        }
-                    }
-                    Pixel::Cross => {
-                        if !img_color.eq(&WHITE) {
-                            return false;
-                        }
-                    }
+        if self.width() != other.width as usize || self.height() != other.height as usize {
+            return false;
+        }
+        if other.cells.is_none() {
+            return (0..self.height()).all(|y| blocks(&self.row(y)) == other.rows[y])
+                && (0..self.width()).all(|x| blocks(&self.column(x)) == other.cols[x]);
+        }
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                let color = match self.get_pixel(x, y) {
+                    Pixel::Color(color) => *color,
+                    Pixel::Cross => BLANK,
+                };
+                if other.solution(x as u32, y as u32) != Some(color) {
+                    return false;
                 }
             }
         }
-        return true
+        true
     }
 }
 
@@ -239,7 +443,8 @@ impl PartialEq<Image> for Board {
 mod tests {
     use super::*;
 
-    const BLACK: Rgb<u8> = Rgb([0x0, 0x0, 0x0]);
+    /// The first color of the black and white pictures
+    const BLACK: u8 = 1;
 
     #[test]
     fn it_creates_game_from_image() {
@@ -268,4 +473,84 @@ mod tests {
         ];
         assert_eq!(&game.cols, &expected);
     }
+
+    #[test]
+    fn it_creates_rectangular_games() {
+        let wide = Image::from_image("test/8x5-fish.png").unwrap();
+        assert_eq!((wide.width, wide.height), (8, 5));
+        assert_eq!(wide.rows.len(), 5);
+        assert_eq!(wide.cols.len(), 8);
+        assert_eq!(&wide.rows[1], &vec![Clue::new(BLACK, 4), Clue::new(BLACK, 1)]);
+        assert_eq!(&wide.cols[7], &vec![Clue::new(BLACK, 3)]);
+
+        let tall = Image::from_image("test/3x7-key.png").unwrap();
+        assert_eq!((tall.width, tall.height), (3, 7));
+        assert_eq!(tall.rows.len(), 7);
+        assert_eq!(tall.cols.len(), 3);
+        assert_eq!(&tall.cols[1], &vec![Clue::new(BLACK, 7)]);
+        assert_eq!(&tall.cols[2], &vec![Clue::new(BLACK, 1), Clue::new(BLACK, 1), Clue::new(BLACK, 1)]);
+
+        // The solution is compared cell by cell, on the whole board
+        let mut board = Board::from(&wide);
+        assert!(board.eq(&wide));
+        board.set_pixel(7, 4, &Pixel::Color(BLACK));
+        assert!(!board.eq(&wide));
+        assert!(!Board::from(&tall).eq(&wide));
+        assert!(!Board::new(5, 8).eq(&wide));
+
+        let clues = Image::from_clues(tall.rows.clone(), tall.cols.clone(), tall.palette.clone());
+        assert!(Board::from(&tall).eq(&clues));
+    }
+
+    #[test]
+    fn it_counts_touching_blocks_of_different_colors() {
+        let red = Rgb([0xFF, 0, 0]);
+        let mut img = RgbImage::from_pixel(4, 1, WHITE);
+        img.put_pixel(0, 0, red);
+        img.put_pixel(1, 0, red);
+        img.put_pixel(2, 0, Rgb([0, 0, 0]));
+        let image = Image::from_rgb_image(img);
+        assert_eq!(image.palette.name(1), "red");
+        assert_eq!(image.palette.name(2), "black");
+        assert_eq!(&image.rows[0], &vec![Clue::new(1, 2), Clue::new(2, 1)]);
+        assert_eq!(&image.cols[1], &vec![Clue::new(1, 1)]);
+    }
+
+    #[test]
+    fn it_changes_the_colors_of_the_palette() {
+        let mut image = Image::from_image("test/4x4-c.png").unwrap();
+        let board = Board::from(&image);
+        assert!(image.set_color(BLACK, Rgb([0, 0, 0x80])));
+        assert_eq!(image.palette.color(BLACK), Rgb([0, 0, 0x80]));
+        assert_eq!(*image.to_rgb_image().unwrap().get_pixel(0, 0), Rgb([0, 0, 0x80]));
+        assert!(board.eq(&image));
+
+        // Two colors can't be the same, the cells of the solution would be mixed up
+        assert!(!image.set_color(BLACK, WHITE));
+        assert_eq!(image.palette.color(BLACK), Rgb([0, 0, 0x80]));
+        assert!(image.set_color(BLANK, Rgb([0xF0, 0xF0, 0xF0])));
+        assert!(board.eq(&image));
+    }
+
+    #[test]
+    fn it_rejects_pictures_not_in_rgb() {
+        let img = DynamicImage::ImageRgba8(image::RgbaImage::new(2, 2));
+        let error = Image::from_dynamic_image(img).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Unsupported image format : Rgba8, the picture must be in RGB"
+        );
+        assert_eq!(format!("{:?}", error), "UnsupportedFormatError(Rgba8)");
+    }
+
+    #[test]
+    fn it_rejects_pictures_with_too_many_colors() {
+        let img = RgbImage::from_fn(16, 16, |x, y| Rgb([x as u8, y as u8, 0]));
+        let result = Image::from_dynamic_image(DynamicImage::ImageRgb8(img.clone()));
+        assert!(matches!(result, Err(ImageError::TooManyColorsError)));
+
+        let img = RgbImage::from_fn(16, 16, |x, y| if x + y == 0 { WHITE } else { img[(x, y)] });
+        let image = Image::from_dynamic_image(DynamicImage::ImageRgb8(img)).unwrap();
+        assert_eq!(image.palette.len(), 256);
+    }
 }
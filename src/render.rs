@@ -0,0 +1,471 @@
+use std::fmt::Write;
+
+use image::{Rgb, RgbImage};
+
+use crate::board::{Board, Pixel};
+use crate::game::satisfied_clues;
+use crate::palette::{Palette, BLANK};
+use crate::picross_image::{Clue, Image, WHITE};
+use crate::solver::GameView;
+
+const GRID: Rgb<u8> = Rgb([0x80, 0x80, 0x80]);
+const CROSS: Rgb<u8> = Rgb([0xC0, 0x40, 0x40]);
+const HIGHLIGHT: Rgb<u8> = Rgb([0xFF, 0x8C, 0x00]);
+/// Color of the clues already matched by the board
+const SATISFIED: Rgb<u8> = Rgb([0xC0, 0xC0, 0xC0]);
+
+/// Size of a cell, in pixels
+const CELL: u32 = 16;
+
+/// Position of the grid in the rendered picture : the clues are drawn on the top and left sides
+struct Layout {
+    left: u32,
+    top: u32,
+    width: u32,
+    height: u32,
+}
+
+impl Layout {
+    fn new(image: &Image) -> Self {
+        let max_len = |clues: &Vec<Vec<Clue>>| clues.iter().map(Vec::len).max().unwrap_or(0) as u32;
+        let left = max_len(&image.rows).max(1) * CELL;
+        let top = max_len(&image.cols).max(1) * CELL;
+        Layout {
+            left,
+            top,
+            width: left + image.width * CELL + 1,
+            height: top + image.height * CELL + 1,
+        }
+    }
+
+    fn cell(&self, x: u32, y: u32) -> (u32, u32) {
+        (self.left + x * CELL, self.top + y * CELL)
+    }
+
+    /// Cells where the clues of the row `y` are drawn, from right to left
+    fn row_clue(&self, y: u32, n: u32) -> (u32, u32) {
+        (self.left - (n + 1) * CELL, self.top + y * CELL)
+    }
+
+    /// Cells where the clues of the column `x` are drawn, from bottom to top
+    fn col_clue(&self, x: u32, n: u32) -> (u32, u32) {
+        (self.left + x * CELL, self.top - (n + 1) * CELL)
+    }
+}
+
+/// Call `f` with the position of every clue, and its color : grey if the board
+/// already matches it
+fn for_each_clue<F>(image: &Image, board: &Board, layout: &Layout, mut f: F)
+where
+    F: FnMut((u32, u32), &Clue, Rgb<u8>),
+{
+    let color = |clue: &Clue, satisfied: bool| {
+        if satisfied { SATISFIED } else { image.palette.color(clue.color) }
+    };
+    for (y, clues) in image.rows.iter().enumerate() {
+        let satisfied = satisfied_clues(clues, &board.row(y));
+        for (n, (clue, done)) in clues.iter().zip(satisfied).rev().enumerate() {
+            f(layout.row_clue(y as u32, n as u32), clue, color(clue, done));
+        }
+    }
+    for (x, clues) in image.cols.iter().enumerate() {
+        let satisfied = satisfied_clues(clues, &board.column(x));
+        for (n, (clue, done)) in clues.iter().zip(satisfied).rev().enumerate() {
+            f(layout.col_clue(x as u32, n as u32), clue, color(clue, done));
+        }
+    }
+}
+
+fn hex(color: &Rgb<u8>) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
+/// Render the puzzle as a SVG document : the clues, and the board state
+pub fn to_svg(image: &Image, board: &Board) -> String {
+    let layout = Layout::new(image);
+    let mut svg = String::new();
+    // Writing to a String can't fail
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = layout.width,
+        h = layout.height
+    );
+    let _ = writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#);
+
+    for_each_clue(image, board, &layout, |(x, y), clue, color| {
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="{}" font-family="sans-serif" font-size="{}" text-anchor="middle" fill="{}">{}</text>"#,
+            x + CELL / 2,
+            y + CELL - 3,
+            CELL - 4,
+            hex(&color),
+            clue.count
+        );
+    });
+
+    for y in 0..board.height() {
+        for x in 0..board.width() {
+            let (px, py) = layout.cell(x as u32, y as u32);
+            match board.get_pixel(x, y) {
+                Pixel::Color(color) if *color != BLANK => {
+                    let _ = writeln!(
+                        svg,
+                        r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                        px, py, CELL, CELL, hex(&image.palette.color(*color))
+                    );
+                }
+                Pixel::Color(_) => {}
+                Pixel::Cross => {
+                    let _ = writeln!(
+                        svg,
+                        r#"<path d="M{} {}l{} {}M{} {}l{} -{}" stroke="{}"/>"#,
+                        px + 4, py + 4, CELL - 8, CELL - 8,
+                        px + 4, py + CELL - 4, CELL - 8, CELL - 8,
+                        hex(&CROSS)
+                    );
+                }
+            }
+        }
+    }
+
+    for x in 0..=image.width {
+        let (px, py) = layout.cell(x, 0);
+        let _ = writeln!(
+            svg,
+            r#"<line x1="{px}" y1="{py}" x2="{px}" y2="{}" stroke="{}"/>"#,
+            py + image.height * CELL,
+            hex(&GRID)
+        );
+    }
+    for y in 0..=image.height {
+        let (px, py) = layout.cell(0, y);
+        let _ = writeln!(
+            svg,
+            r#"<line x1="{px}" y1="{py}" x2="{}" y2="{py}" stroke="{}"/>"#,
+            px + image.width * CELL,
+            hex(&GRID)
+        );
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// 3x5 bitmap font for the digits, one row per byte
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b010, 0b010, 0b010],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+/// The squares drawing a number centered in a cell of size 1, as `[x, y, width, height]`
+pub fn number_squares(n: u32) -> Vec<[f64; 4]> {
+    let digits: Vec<usize> = n.to_string().bytes().map(|b| (b - b'0') as usize).collect();
+    let columns = digits.len() * 4 - 1;
+    let unit = 0.8 / columns.max(5) as f64;
+    let left = (1.0 - columns as f64 * unit) / 2.0;
+    let top = (1.0 - 5.0 * unit) / 2.0;
+    let mut squares = vec![];
+    for (i, digit) in digits.iter().enumerate() {
+        for (row, bits) in DIGITS[*digit].iter().enumerate() {
+            for col in 0..3 {
+                if bits & (0b100 >> col) != 0 {
+                    let x = left + (i * 4 + col) as f64 * unit;
+                    squares.push([x, top + row as f64 * unit, unit, unit]);
+                }
+            }
+        }
+    }
+    squares
+}
+
+fn fill_rect(img: &mut RgbImage, x: u32, y: u32, width: u32, height: u32, color: Rgb<u8>) {
+    for py in y..y + height {
+        for px in x..x + width {
+            img.put_pixel(px, py, color);
+        }
+    }
+}
+
+/// Draw a number centered in the cell at (x, y)
+fn draw_number(img: &mut RgbImage, x: u32, y: u32, n: u32, color: Rgb<u8>) {
+    let digits: Vec<usize> = n.to_string().bytes().map(|b| (b - b'0') as usize).collect();
+    let scale = if digits.len() > 2 { 1 } else { 2 };
+    let width = digits.len() as u32 * 4 * scale - scale;
+    let mut dx = x + CELL.saturating_sub(width) / 2;
+    let dy = y + (CELL - 5 * scale) / 2;
+    for digit in digits {
+        for (row, bits) in DIGITS[digit].iter().enumerate() {
+            for col in 0..3 {
+                if bits & (0b100 >> col) != 0 {
+                    fill_rect(img, dx + col * scale, dy + row as u32 * scale, scale, scale, color);
+                }
+            }
+        }
+        dx += 4 * scale;
+    }
+}
+
+/// Render the puzzle as a picture : the clues, and the board state
+pub fn to_png(image: &Image, board: &Board) -> RgbImage {
+    let layout = Layout::new(image);
+    let mut img = RgbImage::from_pixel(layout.width, layout.height, WHITE);
+
+    for_each_clue(image, board, &layout, |(x, y), clue, color| {
+        draw_number(&mut img, x, y, clue.count, color);
+    });
+
+    for y in 0..board.height() {
+        for x in 0..board.width() {
+            let (px, py) = layout.cell(x as u32, y as u32);
+            match board.get_pixel(x, y) {
+                Pixel::Color(color) => {
+                    fill_rect(&mut img, px, py, CELL, CELL, image.palette.color(*color))
+                }
+                Pixel::Cross => {
+                    for i in 4..CELL - 4 {
+                        img.put_pixel(px + i, py + i, CROSS);
+                        img.put_pixel(px + CELL - 1 - i, py + i, CROSS);
+                    }
+                }
+            }
+        }
+    }
+
+    for x in 0..=image.width {
+        let (px, py) = layout.cell(x, 0);
+        fill_rect(&mut img, px, py, 1, image.height * CELL + 1, GRID);
+    }
+    for y in 0..=image.height {
+        let (px, py) = layout.cell(0, y);
+        fill_rect(&mut img, px, py, image.width * CELL + 1, 1, GRID);
+    }
+    img
+}
+
+/// Number of cells between two separators of the grid, in the terminal
+const GROUP: usize = 5;
+
+/// Write the text in the color, as a 24-bit ANSI foreground
+fn paint(text: &str, color: Rgb<u8>, ansi: bool) -> String {
+    if ansi {
+        format!("\x1b[38;2;{};{};{}m{}\x1b[0m", color[0], color[1], color[2], text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// A cell, `width` characters wide. Without `ansi`, a filled cell is drawn with `#` in a
+/// single color puzzle, else with the position of its color in `colors`, in base 36.
+fn terminal_cell(pixel: &Pixel, palette: &Palette, colors: &[u8], width: usize, ansi: bool) -> String {
+    match pixel {
+        Pixel::Color(BLANK) => " ".repeat(width),
+        Pixel::Color(color) if ansi => {
+            let color = palette.color(*color);
+            format!("\x1b[48;2;{};{};{}m{}\x1b[0m", color[0], color[1], color[2], " ".repeat(width))
+        }
+        Pixel::Color(color) => {
+            let position = colors.iter().position(|c| c == color);
+            let symbol = match position.and_then(|n| char::from_digit(n as u32, 36)) {
+                Some(symbol) if colors.len() > 1 => symbol,
+                _ => '#',
+            };
+            symbol.to_string().repeat(width)
+        }
+        Pixel::Cross => paint(&format!("{:>width$}", "x"), CROSS, ansi),
+    }
+}
+
+/// A line of the grid : `cell` gives the text of the column x, with a separator every
+/// few cells
+fn grid_line<F>(width: usize, separator: char, mut cell: F) -> String
+where
+    F: FnMut(usize) -> String,
+{
+    let mut line = String::new();
+    for x in 0..width {
+        if x > 0 && x % GROUP == 0 {
+            line.push(separator);
+        }
+        line.push_str(&cell(x));
+    }
+    line
+}
+
+/// Render the puzzle for a terminal : the column clues stacked above the grid, the row
+/// clues on the left, and a separator every 5 cells.
+///
+/// With `ansi`, the cells and the clues are drawn in their colors with 24-bit ANSI codes,
+/// the clues matched by the board in grey. Else only ASCII characters are used, the cells
+/// of a color puzzle being drawn with the position of their color in [`Image::colors`].
+pub fn to_terminal(image: &Image, board: &Board, ansi: bool) -> String {
+    to_terminal_highlighted(image, board, None, ansi)
+}
+
+/// Render the puzzle for a terminal like [`to_terminal`], marking the highlighted row
+/// with `<` on its right, or the highlighted column with `^` under the grid
+pub fn to_terminal_highlighted(
+    image: &Image,
+    board: &Board,
+    highlight: Option<(GameView, usize)>,
+    ansi: bool,
+) -> String {
+    let color = |clue: &Clue, satisfied: bool| {
+        if satisfied { SATISFIED } else { image.palette.color(clue.color) }
+    };
+    let mut rows = vec![];
+    for (y, clues) in image.rows.iter().enumerate() {
+        let satisfied = satisfied_clues(clues, &board.row(y));
+        let texts: Vec<String> = clues.iter().map(|clue| clue.count.to_string()).collect();
+        let painted: Vec<String> = texts
+            .iter()
+            .zip(clues.iter().zip(satisfied))
+            .map(|(text, (clue, done))| paint(text, color(clue, done), ansi))
+            .collect();
+        rows.push((texts.join(" ").len(), painted.join(" ")));
+    }
+    let left = rows.iter().map(|(len, _)| *len).max().unwrap_or(0);
+    let columns: Vec<(&Vec<Clue>, Vec<bool>)> = image
+        .cols
+        .iter()
+        .enumerate()
+        .map(|(x, clues)| (clues, satisfied_clues(clues, &board.column(x))))
+        .collect();
+    let top = image.cols.iter().map(Vec::len).max().unwrap_or(0);
+    // The cells are as wide as the widest column clue, and at least 2 characters
+    let clues = image.cols.iter().flatten();
+    let width = clues.map(|clue| clue.count.to_string().len()).max().unwrap_or(0).max(2);
+    let colors = image.colors();
+
+    let mut text = String::new();
+    for n in 0..top {
+        let line = grid_line(board.width(), ' ', |x| {
+            let (clues, satisfied) = &columns[x];
+            match (n + clues.len()).checked_sub(top) {
+                Some(i) => paint(&format!("{:>width$}", clues[i].count), color(&clues[i], satisfied[i]), ansi),
+                None => " ".repeat(width),
+            }
+        });
+        let _ = writeln!(text, "{:left$} {}", "", line);
+    }
+    let rule = grid_line(board.width(), '+', |_| "-".repeat(width));
+    let _ = writeln!(text, "{:left$}+{}", "", rule);
+    for (y, (len, clues)) in rows.iter().enumerate() {
+        if y > 0 && y % GROUP == 0 {
+            let _ = writeln!(text, "{:left$}|{}", "", rule);
+        }
+        let line = grid_line(board.width(), '|', |x| {
+            terminal_cell(board.get_pixel(x, y), &image.palette, &colors, width, ansi)
+        });
+        let mark = if highlight == Some((GameView::Row, y)) { " <" } else { "" };
+        let _ = writeln!(text, "{:pad$}{}|{}{}", "", clues, line, mark, pad = left - len);
+    }
+    if let Some((GameView::Column, x)) = highlight {
+        // Under the last character of the cell, like the clues
+        let end = left + 1 + (x + 1) * width + x / GROUP;
+        let _ = writeln!(text, "{:>end$}", "^");
+    }
+    text
+}
+
+/// Draw a border around the given cells of a picture made by [`to_png`]
+pub fn highlight(img: &mut RgbImage, image: &Image, cells: &[(usize, usize)]) {
+    let layout = Layout::new(image);
+    for &(x, y) in cells {
+        let (px, py) = layout.cell(x as u32, y as u32);
+        fill_rect(img, px, py, CELL + 1, 2, HIGHLIGHT);
+        fill_rect(img, px, py + CELL - 1, CELL + 1, 2, HIGHLIGHT);
+        fill_rect(img, px, py, 2, CELL + 1, HIGHLIGHT);
+        fill_rect(img, px + CELL - 1, py, 2, CELL + 1, HIGHLIGHT);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::text;
+
+    use super::*;
+
+    #[test]
+    fn it_renders_the_puzzle() {
+        let image = Image::from_image("test/4x4-shuriken.png").unwrap();
+        let board = Board::from(&image);
+
+        let img = to_png(&image, &board);
+        // 2 clues on the left and on the top, 4 cells, and the last grid line
+        assert_eq!(img.dimensions(), (6 * CELL + 1, 6 * CELL + 1));
+        assert_eq!(*img.get_pixel(2 * CELL + 1, 2 * CELL + 1), Rgb([0, 0, 0]));
+
+        let wide = Image::from_image("test/8x5-fish.png").unwrap();
+        let img = to_png(&wide, &Board::from(&wide));
+        assert_eq!(img.dimensions(), (10 * CELL + 1, 6 * CELL + 1));
+        assert_eq!(*img.get_pixel(9 * CELL + 1, 5 * CELL + 1), WHITE);
+
+        let svg = to_svg(&image, &board);
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<text").count(), 12);
+        assert_eq!(svg.matches(r##"fill="#000000"/>"##).count(), 8);
+        // The board is solved : every clue is greyed out
+        assert_eq!(svg.matches(r##"fill="#c0c0c0">"##).count(), 12);
+        let svg = to_svg(&image, &Board::new(4, 4));
+        assert_eq!(svg.matches(r##"fill="#000000">"##).count(), 12);
+    }
+
+    #[test]
+    fn it_renders_the_puzzle_for_a_terminal() {
+        let image = Image::from_image("test/4x4-shuriken.png").unwrap();
+        let mut board = Board::new(4, 4);
+        board.set_pixel(0, 0, &Pixel::Color(1));
+        board.set_pixel(2, 0, &Pixel::Cross);
+        assert_eq!(
+            to_terminal(&image, &board, false),
+            concat!(
+                "     1     2\n",
+                "     2 1 1 1\n",
+                "   +--------\n",
+                "2 1|##   x  \n",
+                "  1|        \n",
+                "  1|        \n",
+                "1 2|        \n",
+            )
+        );
+        assert!(to_terminal(&image, &board, true).contains("\x1b[48;2;0;0;0m  \x1b[0m"));
+
+        // A separator every 5 cells
+        let wide = Image::from_image("test/8x5-fish.png").unwrap();
+        let text = to_terminal(&wide, &Board::new(8, 5), false);
+        assert!(text.lines().all(|line| line.is_ascii()));
+        assert!(text.contains("+----------+------"));
+        assert!(text.lines().last().unwrap().ends_with("|          |      "));
+
+        // The cells of a color puzzle show the position of their color
+        let colored = text::parse_image("rb\n").unwrap();
+        let filled = text::parse_board("rb\n", &colored.palette).unwrap();
+        assert_eq!(to_terminal(&colored, &filled, false).lines().last(), Some("1 1|0011"));
+
+        // The highlighted lines are marked
+        let text = to_terminal_highlighted(&image, &board, Some((GameView::Row, 1)), false);
+        assert_eq!(text.lines().nth(4), Some("  1|         <"));
+        let text = to_terminal_highlighted(&image, &board, Some((GameView::Column, 2)), false);
+        assert_eq!(text.lines().last(), Some("         ^"));
+        let text = to_terminal_highlighted(&wide, &Board::new(8, 5), Some((GameView::Column, 6)), false);
+        assert_eq!(text.lines().last().unwrap().len(), text.lines().nth(4).unwrap().len() - 2);
+
+        // The cells get wider for the column clues of 100 cells or more
+        let tall = text::parse_image(&format!("##\n{}", "#.\n".repeat(99))).unwrap();
+        let mut board = Board::new(2, 100);
+        board.set_pixel(0, 0, &Pixel::Color(1));
+        board.set_pixel(1, 1, &Pixel::Cross);
+        let text = to_terminal(&tall, &board, false);
+        let lines: Vec<&str> = text.lines().take(4).collect();
+        assert_eq!(lines, vec!["  100  1", " +------", "2|###   ", "1|     x"]);
+    }
+}
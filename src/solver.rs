@@ -1,21 +1,64 @@
-use crate::{board::Pixel, picross_image::Clue, Game, Board};
+use std::error;
+use std::fmt;
+use std::sync::Mutex;
 
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::{board::Pixel, palette::BLANK, picross_image::Clue, Game, Board, Image};
+
+mod bits;
+mod color_placements;
+mod domains;
 mod full_line;
-use self::full_line::FullLine;
+mod placements;
+use self::bits::BitBoard;
+pub use self::color_placements::ColorPlacements;
+use self::color_placements::MAX_COLORS;
+use self::domains::Domains;
+pub use self::full_line::FullLine;
+pub use self::placements::Placements;
 
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
-enum GameView {
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum GameView {
     Row,
     Column,
 }
 
+impl fmt::Display for GameView {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameView::Row => write!(f, "row"),
+            GameView::Column => write!(f, "column"),
+        }
+    }
+}
+
+/// A row or a column of the board, with its clues
 pub struct GameLine<'a> {
     view: GameView,
     board_line: Vec<&'a Pixel>,
-    clues: &'a Vec<Clue>,
+    clues: &'a [Clue],
     index: usize,
 }
 
+impl<'a> GameLine<'a> {
+    pub fn new(view: GameView, index: usize, board_line: Vec<&'a Pixel>, clues: &'a [Clue]) -> Self {
+        GameLine {
+            view,
+            board_line,
+            clues,
+            index,
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Proposition {
     view: GameView,
     line: Vec<Option<Pixel>>,
@@ -31,6 +74,16 @@ impl Proposition {
         }
     }
 
+    /// The line of the proposition
+    pub fn view(&self) -> GameView {
+        self.view
+    }
+
+    /// Index of the row or column
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
     pub fn count_pixel(&self) -> usize {
         self.line.iter().fold(0, |acc, p| {
             match p {
@@ -47,57 +100,249 @@ impl Proposition {
         }
     }
 
-    pub fn merge(&self, board: &mut Board) {
+    /// The cells of the proposition, as (x, y, pixel)
+    pub fn cells(&self) -> Vec<(usize, usize, Pixel)> {
+        let mut cells = vec![];
         for (i, pixel_opt) in self.line.iter().enumerate() {
             if let Some(pixel) = pixel_opt {
                 let (x, y) = self.get_position(i);
-                board.set_pixel(x, y, pixel);
+                cells.push((x, y, *pixel));
             }
         }
+        cells
+    }
+
+    pub fn merge(&self, board: &mut Board) {
+        for (x, y, pixel) in self.cells() {
+            board.set_pixel(x, y, &pixel);
+        }
+    }
+}
+
+/// Why the cells of a line can't match its clues
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reason {
+    /// The clues and the gaps between them are longer than the line
+    TooLong,
+    /// The cell at this position doesn't match the only possible placement of the clues
+    WrongCell(usize),
+    /// No placement of the clues fits the filled and crossed cells
+    NoPlacement,
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Reason::TooLong => write!(f, "the clues don't fit in the line"),
+            Reason::WrongCell(position) => write!(f, "the cell {} is wrong", position),
+            Reason::NoPlacement => write!(f, "the clues can't be placed"),
+        }
+    }
+}
+
+/// The board state can't satisfy the clues of a line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Contradiction {
+    pub view: GameView,
+    pub index: usize,
+    pub reason: Reason,
+}
+
+impl Contradiction {
+    pub fn new(line: &GameLine, reason: Reason) -> Self {
+        Contradiction {
+            view: line.view,
+            index: line.index,
+            reason,
+        }
+    }
+}
+
+impl fmt::Display for Contradiction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Contradiction on {} {} : {}", self.view, self.index, self.reason)
+    }
+}
+
+impl error::Error for Contradiction {}
+
+pub type Result<T> = std::result::Result<T, Contradiction>;
+
+/// An algorithm looking at a single line. It must be `Sync`, so the lines can be solved
+/// in parallel.
+pub trait SolverAlgo: Sync {
+    /// Name of the algorithm, used in the reports
+    fn name(&self) -> &'static str;
+
+    /// Look for cells of the line that can be deduced from the clues.
+    ///
+    /// Returns `Ok(None)` if nothing can be deduced, and an error if the line can't
+    /// satisfy its clues.
+    fn get_proposition(&self, game_line: &GameLine) -> Result<Option<Proposition>>;
+
+    /// Explain to a player why the cells of the proposition can be deduced
+    fn explain(&self, proposition: &Proposition) -> String {
+        format!("{} found cells of {} {}", self.name(), proposition.view, proposition.index)
+    }
+}
+
+/// The cells found by [`deduce`] when the line algorithms are stuck. It looks at the whole
+/// board, so the propositions are made by the [`Solver`] and not from a single line.
+struct SharedDomains;
+
+impl SolverAlgo for SharedDomains {
+    fn name(&self) -> &'static str {
+        "Domains"
+    }
+
+    fn explain(&self, proposition: &Proposition) -> String {
+        format!(
+            "The colors still possible for the cells of {} {}, shared with the crossing lines, leave a single choice",
+            proposition.view, proposition.index
+        )
+    }
+
+    fn get_proposition(&self, _game_line: &GameLine) -> Result<Option<Proposition>> {
+        Ok(None)
     }
 }
 
-pub trait SolverAlgo {
-    #[deprecated]
-    fn solve(&self, game: &mut Game) -> bool;
+/// A board found by [`deduce`], with the game it was deduced from
+struct Deduced {
+    rows: Vec<Vec<Clue>>,
+    cols: Vec<Vec<Clue>>,
+    from: Board,
+    board: Board,
+}
 
-    fn get_proposition(&self, game_line: &GameLine) -> Option<Proposition>;
+impl Deduced {
+    /// The deduced cells still hold if the clues are the same, and the cells known when
+    /// they were deduced haven't changed
+    fn holds_for(&self, game: &Game) -> bool {
+        let board = &game.board;
+        if self.rows != game.image.rows || self.cols != game.image.cols {
+            return false;
+        }
+        (0..board.height()).all(|y| {
+            (0..board.width()).all(|x| {
+                let known = *self.from.get_pixel(x, y);
+                known == Pixel::Color(BLANK) || known == *board.get_pixel(x, y)
+            })
+        })
+    }
 }
 
 pub struct Solver {
     algos: Vec<Box<dyn SolverAlgo>>,
+    domains: bool,
+    /// The last board found by sharing the domains, so a step by step solve doesn't share
+    /// them again for each of its rows
+    deduced: Mutex<Option<Deduced>>,
 }
 
 pub struct SolverBuilder {
     algos: Vec<Box<dyn SolverAlgo>>,
+    domains: bool,
 }
 
 impl SolverBuilder {
     pub fn new() -> Self {
         SolverBuilder {
-            algos: vec![Box::new(FullLine {})],
+            algos: vec![
+                Box::new(FullLine {}),
+                Box::new(Placements {}),
+                Box::new(ColorPlacements {}),
+            ],
+            domains: true,
         }
     }
 
-    #[allow(dead_code)]
-    pub fn add(mut self, algo: Box<dyn SolverAlgo>) -> SolverBuilder {
+    /// Try this algorithm after the others
+    pub fn with_algo(mut self, algo: Box<dyn SolverAlgo>) -> SolverBuilder {
         self.algos.push(algo);
         self
     }
 
+    /// Share the possible colors of the cells between the rows and the columns once the
+    /// algorithms are stuck, which is on by default
+    pub fn domains(mut self, domains: bool) -> SolverBuilder {
+        self.domains = domains;
+        self
+    }
+
     pub fn build(self) -> Solver {
-        Solver { algos: self.algos }
+        Solver {
+            algos: self.algos,
+            domains: self.domains,
+            deduced: Mutex::default(),
+        }
+    }
+}
+
+impl Default for SolverBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Deduce every cell that can be found by looking at the rows and the columns, sharing
+/// the possible colors of the cells between them.
+///
+/// Returns the board with the deduced cells, the board of the game being left untouched.
+/// Black and white puzzles are solved on a packed board, which is faster on large puzzles.
+/// With more than 31 colors, the sets of possible colors can't hold them all and only the
+/// line algorithms are used.
+pub fn deduce(game: &Game) -> Result<Board> {
+    let colors = game.image.colors();
+    if let [color] = colors[..] {
+        if let Some(mut bits) = BitBoard::from_board(&game.board, color) {
+            bits.propagate(game)?;
+            return Ok(bits.to_board(color));
+        }
+    }
+    if colors.len() > MAX_COLORS {
+        return SolverBuilder::new().build().solve_lines(&game.image, &game.board);
     }
+    let mut domains = Domains::from_board(&game.board, game.image.colors());
+    domains.propagate(game)?;
+    Ok(domains.to_board())
+}
+
+/// The rows of `board` with unknown cells found in `deduced`
+fn new_rows(board: &Board, deduced: &Board) -> Vec<Proposition> {
+    let mut propositions = vec![];
+    for y in 0..board.height() {
+        let line: Vec<Option<Pixel>> = (0..board.width())
+            .map(|x| {
+                let pixel = *deduced.get_pixel(x, y);
+                let unknown = Pixel::Color(BLANK);
+                if *board.get_pixel(x, y) == unknown && pixel != unknown {
+                    Some(pixel)
+                } else {
+                    None
+                }
+            })
+            .collect();
+        if line.iter().any(Option::is_some) {
+            propositions.push(Proposition {
+                view: GameView::Row,
+                line,
+                index: y,
+            });
+        }
+    }
+    propositions
 }
 
 struct RowIterator<'a> {
-    game: &'a Game,
+    image: &'a Image,
+    board: &'a Board,
     y: usize,
 }
 
 impl<'a> RowIterator<'a> {
-    fn new(game: &'a Game) -> Self {
-        RowIterator { game, y: 0 }
+    fn new(image: &'a Image, board: &'a Board) -> Self {
+        RowIterator { image, board, y: 0 }
     }
 }
 
@@ -105,14 +350,14 @@ impl<'a> Iterator for RowIterator<'a> {
     type Item = GameLine<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.y >= self.game.board.height() {
+        if self.y >= self.board.height() {
             return None;
         }
         let mut line = vec![];
-        for x in 0..self.game.board.width() {
-            line.push(self.game.board.get_pixel(x, self.y))
+        for x in 0..self.board.width() {
+            line.push(self.board.get_pixel(x, self.y))
         }
-        let clues = &self.game.image.rows[self.y];
+        let clues = &self.image.rows[self.y];
         let index = self.y;
         self.y += 1;
         Some(GameLine {
@@ -125,13 +370,14 @@ impl<'a> Iterator for RowIterator<'a> {
 }
 
 struct ColumnIterator<'a> {
-    game: &'a Game,
+    image: &'a Image,
+    board: &'a Board,
     x: usize,
 }
 
 impl<'a> ColumnIterator<'a> {
-    fn new(game: &'a Game) -> Self {
-        ColumnIterator { game, x: 0 }
+    fn new(image: &'a Image, board: &'a Board) -> Self {
+        ColumnIterator { image, board, x: 0 }
     }
 }
 
@@ -139,14 +385,14 @@ impl<'a> Iterator for ColumnIterator<'a> {
     type Item = GameLine<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.x >= self.game.board.width() {
+        if self.x >= self.board.width() {
             return None;
         }
         let mut line = vec![];
-        for y in 0..self.game.board.height() {
-            line.push(self.game.board.get_pixel(self.x, y))
+        for y in 0..self.board.height() {
+            line.push(self.board.get_pixel(self.x, y))
         }
-        let clues = &self.game.image.cols[self.x];
+        let clues = &self.image.cols[self.x];
         let index = self.x;
         self.x += 1;
         Some(GameLine {
@@ -159,19 +405,342 @@ impl<'a> Iterator for ColumnIterator<'a> {
 }
 
 impl Solver {
-    pub fn solve(&self, game: &mut Game) -> Option<Proposition> {
+    pub fn solve(&self, game: &Game) -> Result<Option<Proposition>> {
+        Ok(self.step(game)?.map(|(_, proposition)| proposition))
+    }
+
+    /// Get the next proposition, with the name of the algorithm which found it
+    pub fn step(&self, game: &Game) -> Result<Option<(&'static str, Proposition)>> {
+        Ok(self.next(game)?.map(|(algo, proposition)| (algo.name(), proposition)))
+    }
+
+    /// Get the next proposition, with an explanation for a player. The algorithms are
+    /// tried from the simplest one.
+    pub fn hint(&self, game: &Game) -> Result<Option<(String, Proposition)>> {
+        Ok(self.next(game)?.map(|(algo, proposition)| (algo.explain(&proposition), proposition)))
+    }
+
+    fn next(&self, game: &Game) -> Result<Option<(&dyn SolverAlgo, Proposition)>> {
         for algo in &self.algos {
-            for row in RowIterator::new(game) {
-                if let Some(proposition) = algo.get_proposition(&row) {
-                    return Some(proposition);
+            for row in RowIterator::new(&game.image, &game.board) {
+                if let Some(proposition) = algo.get_proposition(&row)? {
+                    return Ok(Some((algo.as_ref(), proposition)));
                 }
             }
-            for col in ColumnIterator::new(game) {
-                if let Some(proposition) = algo.get_proposition(&col) {
-                    return Some(proposition);
+            for col in ColumnIterator::new(&game.image, &game.board) {
+                if let Some(proposition) = algo.get_proposition(&col)? {
+                    return Ok(Some((algo.as_ref(), proposition)));
                 }
             }
         }
-        None
+        let proposition = self.share_domains(game)?.into_iter().next();
+        Ok(proposition.map(|proposition| (&SharedDomains as &dyn SolverAlgo, proposition)))
+    }
+
+    /// The rows with new cells found by [`deduce`], if the domains are shared. A single
+    /// color puzzle is left to the line algorithms, which already find the same cells.
+    ///
+    /// The deduced board is kept until it has no new cell for the game, so the rows can
+    /// be proposed one at a time without sharing the domains again.
+    fn share_domains(&self, game: &Game) -> Result<Vec<Proposition>> {
+        if !self.domains || game.image.colors().len() < 2 {
+            return Ok(vec![]);
+        }
+        let mut cache = self.deduced.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(deduced) = cache.as_ref().filter(|deduced| deduced.holds_for(game)) {
+            let propositions = new_rows(&game.board, &deduced.board);
+            if !propositions.is_empty() {
+                return Ok(propositions);
+            }
+        }
+        let board = deduce(game)?;
+        let propositions = new_rows(&game.board, &board);
+        *cache = Some(Deduced {
+            rows: game.image.rows.clone(),
+            cols: game.image.cols.clone(),
+            from: game.board.clone(),
+            board,
+        });
+        Ok(propositions)
+    }
+
+    /// The proposition of the first algorithm which finds something on the line, with its name
+    fn propose(&self, line: &GameLine) -> Result<Option<(&'static str, Proposition)>> {
+        for algo in &self.algos {
+            if let Some(proposition) = algo.get_proposition(line)? {
+                return Ok(Some((algo.name(), proposition)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// The propositions of the lines, solved in parallel with the `parallel` feature
+    fn propose_all(&self, lines: Vec<GameLine>) -> Result<Vec<(&'static str, Proposition)>> {
+        #[cfg(feature = "parallel")]
+        let lines = lines.into_par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let lines = lines.into_iter();
+        lines.filter_map(|line| self.propose(&line).transpose()).collect()
+    }
+
+    /// Look at all the rows, then at all the columns with the cells found on the rows,
+    /// and return the propositions of every line, with the name of the algorithm which
+    /// found each of them. When the lines find nothing, the rows with cells found by
+    /// sharing the domains are returned instead. An empty result means the solver is stuck.
+    ///
+    /// The rows don't depend on each other, neither do the columns : with the `parallel`
+    /// feature, each half of the sweep is solved in parallel. A line has at most one
+    /// proposition, so the propositions don't conflict and can all be merged, in order.
+    pub fn sweep(&self, game: &Game) -> Result<Vec<(&'static str, Proposition)>> {
+        let propositions = self.sweep_lines(&game.image, &game.board)?;
+        if propositions.is_empty() {
+            let name = SharedDomains.name();
+            return Ok(self.share_domains(game)?.into_iter().map(|p| (name, p)).collect());
+        }
+        Ok(propositions)
+    }
+
+    /// Solve the game sweep after sweep until the solver is stuck, merging the propositions
+    /// on the board. This is faster than a loop on [`Solver::step`], which looks at the
+    /// lines again after each proposition.
+    ///
+    /// `f` is called after each merged proposition, with the game and the name of the
+    /// algorithm which found it, so the board can be shown one proposition at a time.
+    /// The cells found before a contradiction are left on the board.
+    pub fn run<F>(&self, game: &mut Game, mut f: F) -> Result<()>
+    where
+        F: FnMut(&Game, &'static str, &Proposition),
+    {
+        loop {
+            let propositions = self.sweep(game)?;
+            if propositions.is_empty() {
+                return Ok(());
+            }
+            for (name, proposition) in &propositions {
+                proposition.merge(&mut game.board);
+                f(game, name, proposition);
+            }
+        }
+    }
+
+    /// The propositions of the algorithms on the rows, then on the columns
+    fn sweep_lines(&self, image: &Image, board: &Board) -> Result<Vec<(&'static str, Proposition)>> {
+        let rows = self.propose_all(RowIterator::new(image, board).collect())?;
+        let mut board = board.clone();
+        for (_, proposition) in &rows {
+            proposition.merge(&mut board);
+        }
+        let cols = self.propose_all(ColumnIterator::new(image, &board).collect())?;
+        Ok(rows.into_iter().chain(cols).collect())
+    }
+
+    /// The board with every cell the algorithms find, sweep after sweep
+    fn solve_lines(&self, image: &Image, board: &Board) -> Result<Board> {
+        let mut board = board.clone();
+        loop {
+            let propositions = self.sweep_lines(image, &board)?;
+            if propositions.is_empty() {
+                return Ok(board);
+            }
+            for (_, proposition) in propositions {
+                proposition.merge(&mut board);
+            }
+        }
+    }
+
+    /// Find every line of the board which can't satisfy its clues, rows first.
+    ///
+    /// Only the clues are used, not the solution of the puzzle.
+    pub fn check(&self, game: &Game) -> Vec<Contradiction> {
+        RowIterator::new(&game.image, &game.board)
+            .chain(ColumnIterator::new(&game.image, &game.board))
+            .filter_map(|line| {
+                self.algos
+                    .iter()
+                    .find_map(|algo| algo.get_proposition(&line).err())
+            })
+            .collect()
+    }
+
+    /// Names of the algorithms, in the order they are tried, and of the shared domains
+    pub fn algo_names(&self) -> Vec<&'static str> {
+        let mut names: Vec<&'static str> = self.algos.iter().map(|algo| algo.name()).collect();
+        if self.domains {
+            names.push(SharedDomains.name());
+        }
+        names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgb, RgbImage};
+    use proptest::prelude::*;
+
+    use crate::generate::random_image;
+    use crate::picross_image::WHITE;
+    use crate::text;
+
+    use super::*;
+
+    /// A random picture of up to 10x10 cells, in black and white or with 2 colors
+    fn pictures() -> impl Strategy<Value = RgbImage> {
+        (1..=10_u32, 1..=10_u32, 1..=2_u8).prop_flat_map(|(width, height, colors)| {
+            let palette = [WHITE, Rgb([0, 0, 0]), Rgb([0xFF, 0, 0])];
+            prop::collection::vec(0..=colors, (width * height) as usize).prop_map(move |cells| {
+                RgbImage::from_fn(width, height, |x, y| {
+                    palette[cells[(x + y * width) as usize] as usize]
+                })
+            })
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn it_only_proposes_cells_of_the_solution(img in pictures()) {
+            let image = Image::from_rgb_image(img);
+            let solution = Board::from(&image);
+            let solver = SolverBuilder::new().build();
+            let mut game = Game::with_image(image.clone());
+            while let Some(proposition) = solver.solve(&game).unwrap() {
+                for (x, y, pixel) in proposition.cells() {
+                    prop_assert_eq!(pixel, *solution.get_pixel(x, y));
+                }
+                proposition.merge(&mut game.board);
+            }
+
+            // A completed board gives the clues of the puzzle, even if it isn't the picture
+            if game.board.count_unknown() == 0 {
+                let Image { rows, cols, palette, .. } = image;
+                prop_assert!(game.board.eq(&Image::from_clues(rows, cols, palette)));
+            }
+        }
+
+        #[test]
+        fn it_deduces_cells_of_the_solution(img in pictures()) {
+            let game = Game::with_image(Image::from_rgb_image(img));
+            let solution = Board::from(&game.image);
+            let board = deduce(&game).unwrap();
+            for y in 0..board.height() {
+                for x in 0..board.width() {
+                    let pixel = *board.get_pixel(x, y);
+                    prop_assert!(pixel == Pixel::Color(BLANK) || pixel == *solution.get_pixel(x, y));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn it_shares_the_domains_once_the_lines_are_stuck() {
+        // Every line has a single color, so the line algorithms can't place anything
+        let mut game = Game::with_image(text::parse_image("b.\n.r\n").unwrap());
+        let solver = SolverBuilder::new().build();
+        let (name, proposition) = solver.step(&game).unwrap().unwrap();
+        assert_eq!(name, "Domains");
+        assert_eq!(proposition.view(), GameView::Row);
+        assert!(solver.hint(&game).unwrap().unwrap().0.contains("crossing lines"));
+        assert_eq!(solver.algo_names().last(), Some(&"Domains"));
+        assert_eq!(solver.sweep(&game).unwrap().len(), 2);
+
+        while let Some(proposition) = solver.solve(&game).unwrap() {
+            proposition.merge(&mut game.board);
+        }
+        assert!(game.is_finished());
+
+        let solver = SolverBuilder::new().domains(false).build();
+        let game = Game::with_image(game.image);
+        assert!(solver.solve(&game).unwrap().is_none());
+        assert!(!solver.algo_names().contains(&"Domains"));
+
+        // An added algorithm is tried after the others, before the domains
+        let solver = SolverBuilder::new().with_algo(Box::new(FullLine {})).build();
+        assert_eq!(solver.algo_names(), ["FullLine", "Placements", "ColorPlacements", "FullLine", "Domains"]);
+    }
+
+    #[test]
+    fn it_unblocks_color_puzzles_with_the_shared_domains() {
+        let image = text::parse_image("r##.\n#r..\n..##\nr...\n").unwrap();
+        let mut game = Game::with_image(image.clone());
+        let solver = SolverBuilder::new().domains(false).build();
+        while let Some(proposition) = solver.solve(&game).unwrap() {
+            proposition.merge(&mut game.board);
+        }
+        let stuck = text::board_to_text(&game.board, &image.palette).unwrap();
+        assert_eq!(stuck, "??#?\n??.?\n????\n????\n");
+
+        let solver = SolverBuilder::new().build();
+        let mut names = vec![];
+        while !game.is_finished() {
+            let (name, proposition) = solver.step(&game).unwrap().unwrap();
+            proposition.merge(&mut game.board);
+            names.push(name);
+        }
+        assert_eq!(names[0], "Domains");
+        assert!(names.iter().filter(|&&name| name == "Domains").count() > 1);
+
+        // The domains were shared once, from the stuck board
+        let deduced = solver.deduced.lock().unwrap();
+        let from = &deduced.as_ref().unwrap().from;
+        assert_eq!(text::board_to_text(from, &image.palette).unwrap(), stuck);
+    }
+
+    #[test]
+    fn it_deduces_puzzles_with_too_many_colors() {
+        let img = RgbImage::from_fn(40, 2, |x, y| Rgb([x as u8 * 5, y as u8 * 100, 0]));
+        let game = Game::with_image(Image::from_rgb_image(img));
+        assert!(game.image.colors().len() > MAX_COLORS);
+        let board = deduce(&game).unwrap();
+        assert_eq!(board.count_unknown(), 0);
+        assert!(board.eq(&game.image));
+    }
+
+    #[test]
+    fn it_sweeps_the_lines() {
+        let solver = SolverBuilder::new().build();
+        let images = [random_image(30, 20, 0.6, 7), text::parse_image("rrb.\n.bbr\nrb.b\n").unwrap()];
+        for image in images {
+            let mut game = Game::with_image(image.clone());
+            while let Some(proposition) = solver.solve(&game).unwrap() {
+                proposition.merge(&mut game.board);
+            }
+
+            let mut swept = Game::with_image(image);
+            let propositions = solver.sweep(&swept).unwrap();
+            assert!(propositions.iter().any(|(_, p)| p.view() == GameView::Row));
+            let mut steps = 0;
+            solver
+                .run(&mut swept, |game, name, proposition| {
+                    assert!(solver.algo_names().contains(&name));
+                    for (x, y, pixel) in proposition.cells() {
+                        assert_eq!(*game.board.get_pixel(x, y), pixel);
+                    }
+                    steps += 1;
+                })
+                .unwrap();
+            assert!(steps >= propositions.len());
+            let palette = &game.image.palette;
+            assert_eq!(
+                text::board_to_text(&swept.board, palette).unwrap(),
+                text::board_to_text(&game.board, palette).unwrap()
+            );
+        }
+
+        let mut game = Game::with_image(text::parse_image("#.\n").unwrap());
+        game.board = text::parse_board("##\n", &game.image.palette).unwrap();
+        assert_eq!(solver.sweep(&game).err().unwrap().view, GameView::Row);
+        assert_eq!(solver.run(&mut game, |_, _, _| {}).err().unwrap().view, GameView::Row);
+    }
+
+    #[test]
+    fn it_solves_rectangular_games() {
+        let solver = SolverBuilder::new().build();
+        for filename in ["test/8x5-fish.png", "test/3x7-key.png"] {
+            let mut game = Game::new(filename).unwrap();
+            while let Some(proposition) = solver.solve(&game).unwrap() {
+                proposition.merge(&mut game.board);
+            }
+            assert_eq!(game.board.count_unknown(), 0);
+            assert!(game.is_finished());
+        }
     }
 }
@@ -0,0 +1,531 @@
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::board::{Board, Pixel};
+use crate::game::Game;
+use crate::palette::BLANK;
+use crate::picross_image::Clue;
+
+use super::{Contradiction, GameView, Reason, Result};
+
+const WORD: usize = u64::BITS as usize;
+
+/// Call `f` with the index and the mask of every word covering the cells `start..end`
+fn for_each_word<F>(start: usize, end: usize, mut f: F)
+where
+    F: FnMut(usize, u64),
+{
+    let mut i = start;
+    while i < end {
+        let word = i / WORD;
+        let last = end.min((word + 1) * WORD);
+        let width = last - i;
+        let mask = if width == WORD { u64::MAX } else { ((1 << width) - 1) << (i % WORD) };
+        f(word, mask);
+        i = last;
+    }
+}
+
+/// The positions `i` of `bits` at which `bits[i + shift]` is set
+fn shift_down(bits: &[u64], shift: usize) -> Vec<u64> {
+    let (words, offset) = (shift / WORD, shift % WORD);
+    let word = |w: usize| bits.get(w).copied().unwrap_or(0);
+    (0..bits.len())
+        .map(|w| match offset {
+            0 => word(w + words),
+            _ => word(w + words) >> offset | word(w + words + 1) << (WORD - offset),
+        })
+        .collect()
+}
+
+/// The positions `i` of `bits` at which `bits[i - shift]` is set. The bits shifted past
+/// the last word are dropped, the ones past the last position must be masked.
+fn shift_up(bits: &[u64], shift: usize) -> Vec<u64> {
+    let (words, offset) = (shift / WORD, shift % WORD);
+    let word = |w: usize| if w < words { 0 } else { bits[w - words] };
+    (0..bits.len())
+        .map(|w| match offset {
+            0 => word(w),
+            _ => word(w) << offset | if w > words { word(w - 1) >> (WORD - offset) } else { 0 },
+        })
+        .collect()
+}
+
+/// The positions `i` at which the `len` bits `i..i + len` are all set
+fn runs(bits: &[u64], len: usize, mask: &[u64]) -> Vec<u64> {
+    if len == 0 {
+        return mask.to_vec();
+    }
+    let mut runs = bits.to_vec();
+    let mut covered = 1;
+    while covered * 2 <= len {
+        let shifted = shift_down(&runs, covered);
+        runs.iter_mut().zip(shifted).for_each(|(r, s)| *r &= s);
+        covered *= 2;
+    }
+    if covered < len {
+        let shifted = shift_down(&runs, len - covered);
+        runs.iter_mut().zip(shifted).for_each(|(r, s)| *r &= s);
+    }
+    runs
+}
+
+/// The positions `i` at which one of the bits `i + 1 - len..=i` is set
+fn spread(bits: &[u64], len: usize) -> Vec<u64> {
+    if len == 0 {
+        return vec![0; bits.len()];
+    }
+    let mut spread = bits.to_vec();
+    let mut covered = 1;
+    while covered * 2 <= len {
+        let shifted = shift_up(&spread, covered);
+        spread.iter_mut().zip(shifted).for_each(|(r, s)| *r |= s);
+        covered *= 2;
+    }
+    if covered < len {
+        let shifted = shift_up(&spread, len - covered);
+        spread.iter_mut().zip(shifted).for_each(|(r, s)| *r |= s);
+    }
+    spread
+}
+
+/// The positions `i` reached from a seed `j >= i` going down through the positions
+/// `i..j` of `through`, a word at a time from the last one
+fn fill_down(seeds: &[u64], through: &[u64]) -> Vec<u64> {
+    let mut filled = vec![0; seeds.len()];
+    let mut carry = false;
+    for w in (0..seeds.len()).rev() {
+        let mut gen = seeds[w] | if carry { through[w] & 1 << (WORD - 1) } else { 0 };
+        let mut pro = through[w];
+        for shift in [1, 2, 4, 8, 16, 32] {
+            gen |= pro & gen >> shift;
+            pro &= pro >> shift;
+        }
+        filled[w] = gen;
+        carry = gen & 1 != 0;
+    }
+    filled
+}
+
+/// The positions `i` reached from a seed `j <= i` going up through the positions
+/// `j + 1..=i` of `through`, a word at a time from the first one
+fn fill_up(seeds: &[u64], through: &[u64]) -> Vec<u64> {
+    let mut filled = vec![0; seeds.len()];
+    let mut carry = false;
+    for w in 0..seeds.len() {
+        let mut gen = seeds[w] | if carry { through[w] & 1 } else { 0 };
+        let mut pro = through[w];
+        for shift in [1, 2, 4, 8, 16, 32] {
+            gen |= pro & gen << shift;
+            pro &= pro << shift;
+        }
+        filled[w] = gen;
+        carry = gen >> (WORD - 1) != 0;
+    }
+    filled
+}
+
+fn and(a: &[u64], b: &[u64]) -> Vec<u64> {
+    a.iter().zip(b).map(|(a, b)| a & b).collect()
+}
+
+fn or_assign(a: &mut [u64], b: &[u64]) {
+    a.iter_mut().zip(b).for_each(|(a, b)| *a |= b);
+}
+
+/// A line of a black and white board, packed in words : a cell is known when its bit is
+/// set in `known`, and then filled when its bit is set in `filled`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct BitLine {
+    len: usize,
+    filled: Vec<u64>,
+    known: Vec<u64>,
+}
+
+impl BitLine {
+    /// A line of unknown cells
+    pub fn new(len: usize) -> Self {
+        let words = len.div_ceil(WORD);
+        BitLine {
+            len,
+            filled: vec![0; words],
+            known: vec![0; words],
+        }
+    }
+
+    /// The cell `i`, if known : true when filled, false when empty
+    pub fn get(&self, i: usize) -> Option<bool> {
+        let bit = 1 << (i % WORD);
+        if self.known[i / WORD] & bit == 0 {
+            None
+        } else {
+            Some(self.filled[i / WORD] & bit != 0)
+        }
+    }
+
+    pub fn set(&mut self, i: usize, filled: bool) {
+        let bit = 1 << (i % WORD);
+        self.known[i / WORD] |= bit;
+        if filled {
+            self.filled[i / WORD] |= bit;
+        } else {
+            self.filled[i / WORD] &= !bit;
+        }
+    }
+}
+
+/// Look at every placement of the clues that fits the line, and return the line with the
+/// cells that are the same in all of them.
+///
+/// The placements are found on sets of positions `0..=len`, packed in words like the
+/// cells, so each step of the search handles 64 positions at once.
+pub(crate) fn solve_line(line: &BitLine, clues: &[u32]) -> std::result::Result<BitLine, Reason> {
+    let n = line.len;
+    let k = clues.len();
+    let words = (n + 1).div_ceil(WORD);
+    let mut mask = vec![0; words];
+    for_each_word(0, n + 1, |word, bits| mask[word] = bits);
+    // The cells which can be empty, or filled, the position `n` being neither
+    let mut can_empty = vec![0; words];
+    let mut can_fill = vec![0; words];
+    for_each_word(0, n, |word, bits| {
+        can_empty[word] = !line.filled[word] & bits;
+        can_fill[word] = !(line.known[word] & !line.filled[word]) & bits;
+    });
+    // Going up to the position `i` needs the cell `i - 1` to be empty
+    let empty_before = and(&shift_up(&can_empty, 1), &mask);
+    let gap = |j: usize| usize::from(j + 1 < k);
+
+    // fit[j] : the positions `i` where the clues j.. can be placed on the cells i..
+    // starts[j] : the positions where the clue j can start, the clues after it fitting
+    let mut end = vec![0; words];
+    end[n / WORD] = 1 << (n % WORD);
+    let mut fit = vec![fill_down(&end, &can_empty)];
+    let mut starts = vec![];
+    for j in (0..k).rev() {
+        let len = clues[j] as usize;
+        let mut start = runs(&can_fill, len, &mask);
+        if gap(j) == 1 {
+            start = and(&start, &shift_down(&can_empty, len));
+        }
+        start = and(&start, &shift_down(&fit[fit.len() - 1], len + gap(j)));
+        fit.push(fill_down(&start, &can_empty));
+        starts.push(start);
+    }
+    fit.reverse();
+    starts.reverse();
+    if fit[0][0] & 1 == 0 {
+        let length = clues.iter().map(|&count| count as usize).sum::<usize>() + k.saturating_sub(1);
+        return Err(if length > n { Reason::TooLong } else { Reason::NoPlacement });
+    }
+
+    // reach : the positions `i` where the clues ..j can be placed on the cells ..i
+    let mut reach = vec![0; words];
+    reach[0] = 1;
+    reach = fill_up(&reach, &empty_before);
+    let mut can_be_filled = vec![0; words];
+    let mut can_be_empty = vec![0; words];
+    for j in 0..=k {
+        let empty = and(&and(&reach, &can_empty), &shift_down(&fit[j], 1));
+        or_assign(&mut can_be_empty, &empty);
+        if j == k {
+            break;
+        }
+        let len = clues[j] as usize;
+        let placed = and(&reach, &starts[j]);
+        or_assign(&mut can_be_filled, &spread(&placed, len));
+        if gap(j) == 1 {
+            or_assign(&mut can_be_empty, &shift_up(&placed, len));
+        }
+        let next = and(&shift_up(&placed, len + gap(j)), &mask);
+        reach = fill_up(&next, &empty_before);
+    }
+
+    let mut solved = line.clone();
+    for_each_word(0, n, |word, mask| {
+        let both = can_be_filled[word] & can_be_empty[word];
+        solved.known[word] |= !both & mask;
+        solved.filled[word] |= can_be_filled[word] & !can_be_empty[word] & mask;
+    });
+    Ok(solved)
+}
+
+/// Solve the dirty lines, setting the new cells in the crossing lines and marking them
+/// as dirty. The lines are solved in parallel with the `parallel` feature.
+fn sweep(
+    view: GameView,
+    lines: &mut [BitLine],
+    others: &mut [BitLine],
+    clues: &[Vec<u32>],
+    dirty: &mut [bool],
+    dirty_others: &mut [bool],
+) -> Result<()> {
+    let indices: Vec<usize> =
+        (0..lines.len()).filter(|&index| std::mem::take(&mut dirty[index])).collect();
+    #[cfg(feature = "parallel")]
+    let solved = indices.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let solved = indices.iter();
+    let solved: Vec<_> = solved.map(|&index| solve_line(&lines[index], &clues[index])).collect();
+    for (index, solved) in indices.into_iter().zip(solved) {
+        let solved = solved.map_err(|reason| Contradiction { view, index, reason })?;
+        let line = &mut lines[index];
+        for (i, other) in others.iter_mut().enumerate() {
+            if line.get(i).is_none() {
+                if let Some(filled) = solved.get(i) {
+                    other.set(index, filled);
+                    dirty_others[i] = true;
+                }
+            }
+        }
+        *line = solved;
+    }
+    Ok(())
+}
+
+/// A black and white board, with a packed line for each row and each column
+pub(crate) struct BitBoard {
+    rows: Vec<BitLine>,
+    cols: Vec<BitLine>,
+}
+
+impl BitBoard {
+    /// The board filled with `color`, or `None` if it has cells of another color
+    pub fn from_board(board: &Board, color: u8) -> Option<Self> {
+        let mut bits = BitBoard {
+            rows: vec![BitLine::new(board.width()); board.height()],
+            cols: vec![BitLine::new(board.height()); board.width()],
+        };
+        for y in 0..board.height() {
+            for x in 0..board.width() {
+                match board.get_pixel(x, y) {
+                    Pixel::Color(BLANK) => {}
+                    Pixel::Color(c) if *c == color => bits.set(x, y, true),
+                    Pixel::Color(_) => return None,
+                    Pixel::Cross => bits.set(x, y, false),
+                }
+            }
+        }
+        Some(bits)
+    }
+
+    /// The board with the known cells, filled with `color`
+    pub fn to_board(&self, color: u8) -> Board {
+        let mut board = Board::new(self.cols.len(), self.rows.len());
+        for (y, row) in self.rows.iter().enumerate() {
+            for x in 0..row.len {
+                match row.get(x) {
+                    Some(true) => board.set_pixel(x, y, &Pixel::Color(color)),
+                    Some(false) => board.set_pixel(x, y, &Pixel::Cross),
+                    None => {}
+                }
+            }
+        }
+        board
+    }
+
+    fn set(&mut self, x: usize, y: usize, filled: bool) {
+        self.rows[y].set(x, filled);
+        self.cols[x].set(y, filled);
+    }
+
+    /// Solve the rows and the columns from their clues until nothing changes, only
+    /// solving again the lines with new cells
+    pub fn propagate(&mut self, game: &Game) -> Result<()> {
+        let counts = |clues: &Vec<Clue>| clues.iter().map(|clue| clue.count).collect();
+        let rows: Vec<Vec<u32>> = game.image.rows.iter().map(counts).collect();
+        let cols: Vec<Vec<u32>> = game.image.cols.iter().map(counts).collect();
+        let mut dirty_rows = vec![true; self.rows.len()];
+        let mut dirty_cols = vec![true; self.cols.len()];
+        while dirty_rows.contains(&true) || dirty_cols.contains(&true) {
+            let (rows_bits, cols_bits) = (&mut self.rows, &mut self.cols);
+            sweep(GameView::Row, rows_bits, cols_bits, &rows, &mut dirty_rows, &mut dirty_cols)?;
+            sweep(GameView::Column, cols_bits, rows_bits, &cols, &mut dirty_cols, &mut dirty_rows)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use crate::generate::random_image;
+    use crate::solver::color_placements;
+    use crate::solver::domains::Domains;
+    use crate::text;
+
+    use super::*;
+
+    fn line(text: &str) -> BitLine {
+        let mut line = BitLine::new(text.len());
+        for (i, c) in text.chars().enumerate() {
+            match c {
+                '#' => line.set(i, true),
+                '.' => line.set(i, false),
+                _ => {}
+            }
+        }
+        line
+    }
+
+    fn as_text(line: &BitLine) -> String {
+        (0..line.len)
+            .map(|i| match line.get(i) {
+                Some(true) => '#',
+                Some(false) => '.',
+                None => '?',
+            })
+            .collect()
+    }
+
+    #[test]
+    fn it_solves_lines_across_words() {
+        assert_eq!(as_text(&solve_line(&line("?????"), &[3]).unwrap()), "??#??");
+        assert_eq!(as_text(&solve_line(&line("?#???"), &[2]).unwrap()), "?#?..");
+        assert_eq!(solve_line(&line("????"), &[2, 2]).err(), Some(Reason::TooLong));
+        assert_eq!(solve_line(&line("#??#"), &[2]).err(), Some(Reason::NoPlacement));
+
+        // A block overlapping the first two words, and one in the third word
+        let text = "?".repeat(150);
+        let solved = solve_line(&line(&text), &[100, 40]).unwrap();
+        let expected = "?".repeat(9) + &"#".repeat(91) + &"?".repeat(10) + &"#".repeat(31) + &"?".repeat(9);
+        assert_eq!(as_text(&solved), expected);
+
+        // Lines ending on a word
+        assert_eq!(as_text(&solve_line(&line(&"?".repeat(64)), &[64]).unwrap()), "#".repeat(64));
+        let expected = "#".repeat(30) + "." + &"#".repeat(33);
+        assert_eq!(as_text(&solve_line(&line(&"?".repeat(64)), &[30, 33]).unwrap()), expected);
+        let solved = solve_line(&line(&("?".repeat(127) + "#")), &[1]).unwrap();
+        assert_eq!(as_text(&solved), ".".repeat(127) + "#");
+    }
+
+    /// Longest lines checked against every placement, the longer ones are checked
+    /// against the placements of the colors
+    const MAX_BRUTE_FORCE: usize = 12;
+
+    /// The lengths of the blocks of `fill`
+    fn counts(fill: impl Iterator<Item = bool>) -> Vec<u32> {
+        let mut counts = vec![];
+        let mut previous = false;
+        for filled in fill {
+            match counts.last_mut() {
+                Some(count) if filled && previous => *count += 1,
+                _ if filled => counts.push(1),
+                _ => {}
+            }
+            previous = filled;
+        }
+        counts
+    }
+
+    /// The cells filled or empty in every placement of the clues that fits `cells`
+    fn every_placement(cells: &[Option<bool>], clues: &[u32]) -> Option<Vec<Option<bool>>> {
+        let n = cells.len();
+        let placements: Vec<u32> = (0..1 << n)
+            .filter(|fill| {
+                let known = cells.iter().enumerate().all(|(i, cell)| {
+                    cell.is_none_or(|filled| (fill >> i & 1 == 1) == filled)
+                });
+                known && counts((0..n).map(|i| fill >> i & 1 == 1)) == clues
+            })
+            .collect();
+        if placements.is_empty() {
+            return None;
+        }
+        let cells = (0..n).map(|i| {
+            let filled = placements.iter().filter(|&fill| fill >> i & 1 == 1).count();
+            match filled {
+                0 => Some(false),
+                filled if filled == placements.len() => Some(true),
+                _ => None,
+            }
+        });
+        Some(cells.collect())
+    }
+
+    /// The cells filled or empty in every placement, from the sets of possible colors
+    fn color_placements(cells: &[Option<bool>], clues: &[u32]) -> Option<Vec<Option<bool>>> {
+        const BLACK: u8 = 1;
+        let palette = [BLACK];
+        let black = color_placements::bit(&palette, &BLACK);
+        let allowed: Vec<u32> = cells
+            .iter()
+            .map(|cell| match cell {
+                Some(true) => black,
+                Some(false) => color_placements::EMPTY,
+                None => black | color_placements::EMPTY,
+            })
+            .collect();
+        let clues: Vec<Clue> = clues.iter().map(|&count| Clue { color: BLACK, count }).collect();
+        let possible = color_placements::narrow(&allowed, &clues, &palette).ok()?;
+        let cells = possible.iter().map(|&set| match set {
+            set if set == black => Some(true),
+            color_placements::EMPTY => Some(false),
+            _ => None,
+        });
+        Some(cells.collect())
+    }
+
+    proptest! {
+        #[test]
+        fn it_keeps_the_cells_of_every_placement(
+            (fill, known, wrong) in (1..=150_usize).prop_flat_map(|n| (
+                prop::collection::vec(any::<bool>(), n),
+                prop::collection::vec(prop::bool::weighted(0.3), n),
+                // A cell against the fill, which may leave no placement
+                prop::option::of(0..n),
+            )),
+        ) {
+            let clues = counts(fill.iter().copied());
+            let cells: Vec<Option<bool>> = (0..fill.len())
+                .map(|i| match wrong {
+                    Some(wrong) if wrong == i => Some(!fill[i]),
+                    _ => known[i].then_some(fill[i]),
+                })
+                .collect();
+            let text: String = cells
+                .iter()
+                .map(|cell| match cell {
+                    Some(true) => '#',
+                    Some(false) => '.',
+                    None => '?',
+                })
+                .collect();
+            let expected = if cells.len() <= MAX_BRUTE_FORCE {
+                every_placement(&cells, &clues)
+            } else {
+                color_placements(&cells, &clues)
+            };
+            match solve_line(&line(&text), &clues) {
+                Err(_) => prop_assert!(expected.is_none()),
+                Ok(solved) => {
+                    prop_assert!(expected.is_some());
+                    let solved: Vec<Option<bool>> = (0..cells.len()).map(|i| solved.get(i)).collect();
+                    prop_assert_eq!(Some(solved), expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn it_deduces_the_same_cells_as_the_domains() {
+        for seed in 0..3 {
+            let game = Game::with_image(random_image(50, 50, 0.6, seed));
+            let color = game.image.colors()[0];
+            let mut bits = BitBoard::from_board(&game.board, color).unwrap();
+            bits.propagate(&game).unwrap();
+            let mut domains = Domains::from_board(&game.board, game.image.colors());
+            domains.propagate(&game).unwrap();
+            let palette = &game.image.palette;
+            assert_eq!(
+                text::board_to_text(&bits.to_board(color), palette).unwrap(),
+                text::board_to_text(&domains.to_board(), palette).unwrap()
+            );
+        }
+
+        let mut game = Game::with_image(text::parse_image("#r\n").unwrap());
+        game.board = text::parse_board("r?\n", &game.image.palette).unwrap();
+        assert!(BitBoard::from_board(&game.board, 1).is_none());
+    }
+}
@@ -0,0 +1,248 @@
+use crate::board::Pixel;
+use crate::palette::BLANK;
+use crate::picross_image::Clue;
+
+use super::{Contradiction, GameLine, Proposition, Reason, Result, SolverAlgo};
+
+/// Bit of the empty cells in the sets of possible colors
+pub(crate) const EMPTY: u32 = 1;
+
+/// Number of colors that fit in the sets of possible colors, besides the empty cell
+pub(crate) const MAX_COLORS: usize = u32::BITS as usize - 1;
+
+/// Bit of a color in the sets of possible colors : `1 << (index + 1)` for the color at
+/// `index` in the colors of the line, 0 if it isn't one of them
+pub(crate) fn bit(palette: &[u8], color: &u8) -> u32 {
+    palette.iter().position(|c| c == color).map_or(0, |index| 2 << index)
+}
+
+/// Narrow the sets of possible colors of the cells of a line, keeping the colors that
+/// appear in at least one placement of the clues.
+///
+/// `allowed` has the set of each cell, with the bits of the palette.
+pub(crate) fn narrow(
+    allowed: &[u32],
+    clues: &[Clue],
+    palette: &[u8],
+) -> std::result::Result<Vec<u32>, Reason> {
+    let n = allowed.len();
+    let k = clues.len();
+
+    // Place the clue `j` at index `i`, and return the index where the next clue can
+    // start : a gap is needed only between two blocks of the same color
+    let fits = |i: usize, j: usize| -> Option<usize> {
+        let end = i + clues[j].count as usize;
+        let color = bit(palette, &clues[j].color);
+        if end > n || !allowed[i..end].iter().all(|&a| a & color != 0) {
+            return None;
+        }
+        match clues.get(j + 1) {
+            Some(next) if next.color.eq(&clues[j].color) => {
+                if end < n && allowed[end] & EMPTY != 0 {
+                    Some(end + 1)
+                } else {
+                    None
+                }
+            }
+            _ => Some(end),
+        }
+    };
+
+    // fit[i][j] : the clues j.. can be placed on the cells i..
+    let mut fit = vec![vec![false; k + 1]; n + 1];
+    fit[n][k] = true;
+    for i in (0..n).rev() {
+        for j in 0..=k {
+            let mut ok = allowed[i] & EMPTY != 0 && fit[i + 1][j];
+            if !ok && j < k {
+                ok = fits(i, j).is_some_and(|next| fit[next][j + 1]);
+            }
+            fit[i][j] = ok;
+        }
+    }
+    if !fit[0][0] {
+        let gaps = clues.windows(2).filter(|w| w[0].color.eq(&w[1].color)).count();
+        let length = clues.iter().map(|clue| clue.count as usize).sum::<usize>() + gaps;
+        return Err(if length > n { Reason::TooLong } else { Reason::NoPlacement });
+    }
+
+    // reach[i][j] : the clues ..j can be placed on the cells ..i
+    let mut reach = vec![vec![false; k + 1]; n + 1];
+    reach[0][0] = true;
+    let mut possible = vec![0_u32; n];
+    for i in 0..n {
+        for j in 0..=k {
+            if !reach[i][j] {
+                continue;
+            }
+            if allowed[i] & EMPTY != 0 && fit[i + 1][j] {
+                possible[i] |= EMPTY;
+                reach[i + 1][j] = true;
+            }
+            if j < k {
+                if let Some(next) = fits(i, j).filter(|&next| fit[next][j + 1]) {
+                    let end = i + clues[j].count as usize;
+                    let color = bit(palette, &clues[j].color);
+                    possible[i..end].iter_mut().for_each(|p| *p |= color);
+                    if next > end {
+                        possible[end] |= EMPTY;
+                    }
+                    reach[next][j + 1] = true;
+                }
+            }
+        }
+    }
+    Ok(possible)
+}
+
+/// Look at every placement of the clues that fits the line, keeping for each cell the set
+/// of its possible colors. A cell is proposed when its set has only one color, or only
+/// the empty cell.
+///
+/// Blocks of different colors may touch. The lines of a single color are left to
+/// [`Placements`](super::placements::Placements), and the lines with more than 31 colors
+/// aren't solved.
+pub struct ColorPlacements;
+
+impl ColorPlacements {
+    /// The colors of the clues, in order of appearance
+    fn palette(game_line: &GameLine) -> Vec<u8> {
+        let mut palette = vec![];
+        for clue in game_line.clues {
+            if !palette.contains(&clue.color) {
+                palette.push(clue.color);
+            }
+        }
+        palette
+    }
+}
+
+impl SolverAlgo for ColorPlacements {
+    fn name(&self) -> &'static str {
+        "ColorPlacements"
+    }
+
+    fn explain(&self, proposition: &Proposition) -> String {
+        format!(
+            "These cells have only one possible color in the placements of the clues of {} {}",
+            proposition.view, proposition.index
+        )
+    }
+
+    fn get_proposition(&self, game_line: &GameLine) -> Result<Option<Proposition>> {
+        let palette = Self::palette(game_line);
+        if palette.len() < 2 || palette.len() > MAX_COLORS {
+            return Ok(None);
+        }
+        // The colors each cell may have, from the board
+        let line = &game_line.board_line;
+        let allowed: Vec<u32> = line
+            .iter()
+            .map(|pixel| match pixel {
+                Pixel::Cross => EMPTY,
+                Pixel::Color(BLANK) => u32::MAX,
+                Pixel::Color(c) => bit(&palette, c),
+            })
+            .collect();
+        let possible = narrow(&allowed, game_line.clues, &palette)
+            .map_err(|reason| Contradiction::new(game_line, reason))?;
+
+        let mut changes = false;
+        let proposition = line
+            .iter()
+            .zip(&possible)
+            .map(|(pixel, &set)| {
+                if **pixel != Pixel::Color(BLANK) || !set.is_power_of_two() {
+                    return None;
+                }
+                changes = true;
+                if set == EMPTY {
+                    Some(Pixel::Cross)
+                } else {
+                    Some(Pixel::Color(palette[set.trailing_zeros() as usize - 1]))
+                }
+            })
+            .collect();
+        if changes {
+            Ok(Some(Proposition::new(proposition, game_line)))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::Game;
+    use crate::solver::placements::Placements;
+    use crate::solver::{GameView, Solver, SolverBuilder};
+    use crate::text;
+
+    use super::*;
+
+    const RED: u8 = 1;
+    const BLUE: u8 = 2;
+    const UNKNOWN: Pixel = Pixel::Color(BLANK);
+
+    fn propose(line: &[Pixel], clues: &[(u8, u32)]) -> Result<Option<Proposition>> {
+        let clues: Vec<Clue> = clues.iter().map(|&(color, count)| Clue { color, count }).collect();
+        let game_line = GameLine {
+            view: GameView::Row,
+            board_line: line.iter().collect(),
+            clues: &clues,
+            index: 0,
+        };
+        ColorPlacements.get_proposition(&game_line)
+    }
+
+    #[test]
+    fn it_lets_blocks_of_different_colors_touch() {
+        // rrb. rr.b .rrb
+        let proposition = propose(&[UNKNOWN; 4], &[(RED, 2), (BLUE, 1)]).unwrap().unwrap();
+        assert_eq!(proposition.line, vec![None, Some(Pixel::Color(RED)), None, None]);
+
+        // Only rrb fits
+        let proposition = propose(&[UNKNOWN; 3], &[(RED, 2), (BLUE, 1)]).unwrap().unwrap();
+        assert_eq!(
+            proposition.line,
+            vec![Some(Pixel::Color(RED)), Some(Pixel::Color(RED)), Some(Pixel::Color(BLUE))]
+        );
+
+        let line = [UNKNOWN, UNKNOWN, Pixel::Color(BLUE), UNKNOWN];
+        let proposition = propose(&line, &[(RED, 1), (BLUE, 1)]).unwrap().unwrap();
+        assert_eq!(proposition.line, vec![None, None, None, Some(Pixel::Cross)]);
+
+        // Single color lines are left to the other algorithms
+        assert!(propose(&[UNKNOWN; 3], &[(RED, 3)]).unwrap().is_none());
+    }
+
+    #[test]
+    fn it_detects_contradictions() {
+        let reason = |line: &[Pixel], clues: &[(u8, u32)]| propose(line, clues).err().unwrap().reason;
+        assert_eq!(reason(&[UNKNOWN; 4], &[(RED, 2), (RED, 1), (BLUE, 1)]), Reason::TooLong);
+        let line = [Pixel::Color(BLUE), UNKNOWN, UNKNOWN];
+        assert_eq!(reason(&line, &[(RED, 1), (BLUE, 1)]), Reason::NoPlacement);
+    }
+
+    #[test]
+    fn it_solves_color_games() {
+        let solution = "rrb.\n.bbr\nrb.b\n";
+        let mut game = Game::with_image(text::parse_image(solution).unwrap());
+        let solver = Solver {
+            algos: vec![Box::new(Placements {}), Box::new(ColorPlacements {})],
+            domains: false,
+            deduced: Default::default(),
+        };
+        while let Some(proposition) = solver.solve(&game).unwrap() {
+            proposition.merge(&mut game.board);
+        }
+        assert_eq!(text::board_to_text(&game.board, &game.image.palette).unwrap(), solution);
+
+        let mut game = Game::with_image(text::parse_image(solution).unwrap());
+        let solver = SolverBuilder::new().build();
+        while let Some(proposition) = solver.solve(&game).unwrap() {
+            proposition.merge(&mut game.board);
+        }
+        assert!(game.is_finished());
+    }
+}
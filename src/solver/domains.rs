@@ -0,0 +1,161 @@
+use crate::board::{Board, Pixel};
+use crate::game::Game;
+use crate::palette::BLANK;
+
+use super::color_placements::{bit, narrow, EMPTY, MAX_COLORS};
+use super::{Contradiction, GameView, Result};
+
+/// The colors still possible for each cell of the board, as a set of bits : bit 0 is the
+/// empty cell, and bit `i + 1` the color `palette[i]`.
+///
+/// Unlike a [`Board`], a cell can be known to be "red or blue", so the rows and the
+/// columns can share what they know of a cell before its color is found.
+pub(crate) struct Domains {
+    width: usize,
+    height: usize,
+    palette: Vec<u8>,
+    cells: Vec<u32>,
+}
+
+impl Domains {
+    /// Every color of the palette, and the empty cell
+    fn all(&self) -> u32 {
+        u32::MAX >> (MAX_COLORS - self.palette.len())
+    }
+
+    /// The sets of the cells of the board, `palette` being the colors of the clues : an
+    /// unknown cell may have any of them, and another color can't be the color of any cell
+    pub fn from_board(board: &Board, palette: Vec<u8>) -> Self {
+        assert!(palette.len() <= MAX_COLORS, "too many colors");
+        let mut domains = Domains {
+            width: board.width(),
+            height: board.height(),
+            palette,
+            cells: vec![],
+        };
+        let all = domains.all();
+        for y in 0..board.height() {
+            for x in 0..board.width() {
+                let set = match board.get_pixel(x, y) {
+                    Pixel::Cross => EMPTY,
+                    Pixel::Color(BLANK) => all,
+                    Pixel::Color(color) => bit(&domains.palette, color),
+                };
+                domains.cells.push(set);
+            }
+        }
+        domains
+    }
+
+    /// The board with the cells having a single possible color, the others being unknown
+    pub fn to_board(&self) -> Board {
+        let mut board = Board::new(self.width, self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let set = self.get(x, y);
+                if set == EMPTY {
+                    board.set_pixel(x, y, &Pixel::Cross);
+                } else if set.is_power_of_two() {
+                    let color = self.palette[set.trailing_zeros() as usize - 1];
+                    board.set_pixel(x, y, &Pixel::Color(color));
+                }
+            }
+        }
+        board
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> u32 {
+        self.cells[x + y * self.width]
+    }
+
+    fn position(&self, view: GameView, index: usize, i: usize) -> usize {
+        match view {
+            GameView::Row => i + index * self.width,
+            GameView::Column => index + i * self.width,
+        }
+    }
+
+    fn line(&self, view: GameView, index: usize) -> Vec<u32> {
+        let len = if view == GameView::Row { self.width } else { self.height };
+        (0..len).map(|i| self.cells[self.position(view, index, i)]).collect()
+    }
+
+    /// Narrow the sets of every row and column from their clues, until nothing changes
+    pub fn propagate(&mut self, game: &Game) -> Result<()> {
+        let lines: Vec<(GameView, usize)> = (0..self.height)
+            .map(|y| (GameView::Row, y))
+            .chain((0..self.width).map(|x| (GameView::Column, x)))
+            .collect();
+        let mut changes = true;
+        while changes {
+            changes = false;
+            for &(view, index) in &lines {
+                let clues = match view {
+                    GameView::Row => &game.image.rows[index],
+                    GameView::Column => &game.image.cols[index],
+                };
+                let line = self.line(view, index);
+                let narrowed = narrow(&line, clues, &self.palette)
+                    .map_err(|reason| Contradiction { view, index, reason })?;
+                for (i, set) in narrowed.into_iter().enumerate() {
+                    if set != line[i] {
+                        let position = self.position(view, index, i);
+                        self.cells[position] = set;
+                        changes = true;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solver::{deduce, Reason, SolverBuilder};
+    use crate::text;
+
+    use super::*;
+
+    #[test]
+    fn it_converts_boards() {
+        let image = text::parse_image("rb\n").unwrap();
+        let board = text::parse_board("r.\n?b\n", &image.palette).unwrap();
+        let domains = Domains::from_board(&board, image.colors());
+        assert_eq!(domains.get(0, 0), 0b010);
+        assert_eq!(domains.get(1, 0), EMPTY);
+        assert_eq!(domains.get(0, 1), 0b111);
+        assert_eq!(domains.get(1, 1), 0b100);
+        assert_eq!(text::board_to_text(&domains.to_board(), &image.palette).unwrap(), "r.\n?b\n");
+    }
+
+    #[test]
+    fn it_shares_the_possible_colors_between_lines() {
+        // Every line has a single color, so the line solvers can't place anything
+        let mut game = Game::with_image(text::parse_image("b.\n.r\n").unwrap());
+        let solver = SolverBuilder::new().domains(false).build();
+        assert!(solver.solve(&game).unwrap().is_none());
+
+        // The cell (0, 1) is red or empty from its row, and blue or empty from its column
+        let mut domains = Domains::from_board(&game.board, game.image.colors());
+        domains.propagate(&game).unwrap();
+        assert_eq!(text::board_to_text(&domains.to_board(), &game.image.palette).unwrap(), "b.\n.r\n");
+
+        game.board = text::parse_board("?b\n??\n", &game.image.palette).unwrap();
+        let mut domains = Domains::from_board(&game.board, game.image.colors());
+        let contradiction = domains.propagate(&game).err().unwrap();
+        assert_eq!(contradiction.reason, Reason::NoPlacement);
+    }
+
+    #[test]
+    fn it_deduces_the_boards() {
+        let solution = "rrb.\n.bbr\nrb.b\n";
+        let game = Game::with_image(text::parse_image(solution).unwrap());
+        assert_eq!(text::board_to_text(&deduce(&game).unwrap(), &game.image.palette).unwrap(), solution);
+
+        let solution = "##.\n.##\n#.#\n";
+        let game = Game::with_image(text::parse_image(solution).unwrap());
+        assert_eq!(text::board_to_text(&deduce(&game).unwrap(), &game.image.palette).unwrap(), solution);
+        assert_eq!(game.board.count_unknown(), 9);
+    }
+}
@@ -1,19 +1,25 @@
 use crate::board::{Pixel};
-use crate::game::Game;
-use crate::picross_image::{WHITE};
+use crate::palette::BLANK;
 
-use super::{GameLine, Proposition, SolverAlgo};
+use super::{Contradiction, GameLine, Proposition, Reason, Result, SolverAlgo};
 
 pub struct FullLine;
 
 impl SolverAlgo for FullLine {
-    fn solve(&self, _game: &mut Game) -> bool {
-        false
+    fn name(&self) -> &'static str {
+        "FullLine"
     }
 
-    fn get_proposition(&self, game_line: &GameLine) -> Option<super::Proposition> {
-        let mut current_color = WHITE;
-        let mut counter = 0 as usize;
+    fn explain(&self, proposition: &Proposition) -> String {
+        format!(
+            "The clues of {} {} and the gaps between them fill the whole line",
+            proposition.view, proposition.index
+        )
+    }
+
+    fn get_proposition(&self, game_line: &GameLine) -> Result<Option<Proposition>> {
+        let mut current_color = BLANK;
+        let mut counter = 0_usize;
 
         // count how many pixel are there can be if they are all collapsed
         for clue in game_line.clues {
@@ -24,55 +30,63 @@ impl SolverAlgo for FullLine {
             current_color = clue.color;
         }
 
-        if counter != game_line.board_line.len() {
-            return None;
+        if counter > game_line.board_line.len() {
+            return Err(Contradiction::new(game_line, Reason::TooLong));
+        }
+        if counter < game_line.board_line.len() {
+            return Ok(None);
         }
 
         // a full line is available
-        let mut current_color = WHITE;
+        let mut current_color = BLANK;
         let mut index = 0;
         let mut proposition = vec![Some(Pixel::Cross); game_line.board_line.len()];
-        let mut changes = false;
         for clue in game_line.clues {
             if clue.color.eq(&current_color) {
                 // 2 consecutive colors, allow a space between them
                 index += 1;
             }
-            for _ in 0..clue.count {
-                // Check if the board already contains this color
-                let board_pixel = game_line.board_line[index];
-                if let Pixel::Color(color) = board_pixel {
-                    if !color.eq(&clue.color) {
-                        changes = true;
-                    }
-                }
-                // Add the color
-                proposition[index] = Some(Pixel::Color(clue.color));
-                index += 1;
+            // Add the color
+            let count = clue.count as usize;
+            for cell in proposition.iter_mut().skip(index).take(count) {
+                *cell = Some(Pixel::Color(clue.color));
             }
+            index += count;
             current_color = clue.color;
         }
+
+        // Check the board against the only possible line
+        let mut changes = false;
+        for (i, (board_pixel, pixel)) in game_line.board_line.iter().zip(&proposition).enumerate() {
+            match (board_pixel, pixel) {
+                (Pixel::Color(color), Some(Pixel::Color(_))) if color.eq(&BLANK) => changes = true,
+                (Pixel::Color(color), _) if color.eq(&BLANK) => {}
+                (board_pixel, Some(pixel)) if *board_pixel != pixel => {
+                    return Err(Contradiction::new(game_line, Reason::WrongCell(i)));
+                }
+                _ => {}
+            }
+        }
         if changes {
-            Some(Proposition {
+            Ok(Some(Proposition {
                 view: game_line.view,
                 line: proposition,
                 index: game_line.index,
-            })
+            }))
         } else {
-            None
+            Ok(None)
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use image::Rgb;
-
+    use crate::game::Game;
     use crate::solver::{Solver, GameView};
 
     use super::*;
 
-    const BLACK: Pixel = Pixel::Color(Rgb([0, 0, 0]));
+    const BLACK: Pixel = Pixel::Color(1);
 
     fn proposition_as_str(proposition: &Proposition) -> String {
         let mut str = String::new();
@@ -82,7 +96,7 @@ mod tests {
                     str.push('X');
                 },
                 Some(Pixel::Color(color)) => {
-                    if color.eq(&WHITE) {
+                    if color.eq(&BLANK) {
                         str.push(' ');
                     }
                     else {
@@ -104,10 +118,12 @@ mod tests {
         let mut game = game_res.unwrap();
         let solver = Solver {
             algos: vec![Box::new(FullLine {})],
+            domains: false,
+            deduced: Default::default(),
         };
 
         // Should return the 1st row
-        let proposition = solver.solve(&mut game);
+        let proposition = solver.solve(&game).unwrap();
         assert!(proposition.is_some());
         let proposition = proposition.unwrap();
         assert_eq!(proposition.view, GameView::Row);
@@ -119,7 +135,7 @@ mod tests {
         game.board.set_pixel(1, 0, &BLACK);
 
         // Should return the 1st row
-        let proposition = solver.solve(&mut game);
+        let proposition = solver.solve(&game).unwrap();
         assert!(proposition.is_some());
         let proposition = proposition.unwrap();
         assert_eq!(proposition.view, GameView::Row);
@@ -130,7 +146,7 @@ mod tests {
         game.board.set_pixel(3, 0, &BLACK);
 
         // Should return the 2nd row
-        let proposition = solver.solve(&mut game);
+        let proposition = solver.solve(&game).unwrap();
         assert!(proposition.is_some());
         let proposition = proposition.unwrap();
         assert_eq!(proposition.view, GameView::Row);
@@ -143,7 +159,7 @@ mod tests {
         game.board.set_pixel(3, 3, &BLACK);
 
         // Should return the 1st col
-        let proposition = solver.solve(&mut game);
+        let proposition = solver.solve(&game).unwrap();
         assert!(proposition.is_some());
         let proposition = proposition.unwrap();
         assert_eq!(proposition.view, GameView::Column);
@@ -154,7 +170,7 @@ mod tests {
         game.board.set_pixel(0, 2, &BLACK);
 
         // Should return the last col
-        let proposition = solver.solve(&mut game);
+        let proposition = solver.solve(&game).unwrap();
         assert!(proposition.is_some());
         let proposition = proposition.unwrap();
         assert_eq!(proposition.view, GameView::Column);
@@ -165,6 +181,28 @@ mod tests {
         game.board.set_pixel(3, 1, &BLACK);
 
         // No more proposition
-        assert!(solver.solve(&mut game).is_none());
+        assert!(solver.solve(&game).unwrap().is_none());
+    }
+
+    #[test]
+    fn it_detects_contradictions() {
+        let mut game = Game::new("test/4x4-shuriken.png").unwrap();
+        let solver = Solver {
+            algos: vec![Box::new(FullLine {})],
+            domains: false,
+            deduced: Default::default(),
+        };
+
+        // A cross where the 1st row must be filled
+        game.board.set_pixel(1, 0, &Pixel::Cross);
+        let contradiction = solver.solve(&game).err().unwrap();
+        assert_eq!(contradiction.view, GameView::Row);
+        assert_eq!(contradiction.index, 0);
+        assert_eq!(contradiction.reason, Reason::WrongCell(1));
+
+        // A filled cell where the 1st row must be empty
+        game.board.set_pixel(1, 0, &BLACK);
+        game.board.set_pixel(2, 0, &BLACK);
+        assert_eq!(solver.solve(&game).err().unwrap().reason, Reason::WrongCell(2));
     }
 }
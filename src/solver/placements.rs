@@ -0,0 +1,165 @@
+use crate::board::Pixel;
+use crate::palette::BLANK;
+
+use super::bits::{solve_line, BitLine};
+use super::{Contradiction, GameLine, Proposition, Reason, Result, SolverAlgo};
+
+/// Look at every placement of the clues that fits the line, and keep the cells
+/// which are filled (or empty) in all of them.
+///
+/// Only single color lines are handled. The cells are packed in words, like the black and
+/// white boards of [`deduce`](super::deduce), to look at many placements at once.
+pub struct Placements;
+
+impl SolverAlgo for Placements {
+    fn name(&self) -> &'static str {
+        "Placements"
+    }
+
+    fn explain(&self, proposition: &Proposition) -> String {
+        format!(
+            "These cells are the same in every placement of the clues of {} {}",
+            proposition.view, proposition.index
+        )
+    }
+
+    fn get_proposition(&self, game_line: &GameLine) -> Result<Option<Proposition>> {
+        let clues = game_line.clues;
+        let color = clues.first().map_or(BLANK, |clue| clue.color);
+        if clues.iter().any(|clue| !clue.color.eq(&color)) {
+            return Ok(None);
+        }
+
+        // A cell of another color can be neither filled nor empty
+        let line = &game_line.board_line;
+        let mut bits = BitLine::new(line.len());
+        let mut other_color = false;
+        for (i, pixel) in line.iter().enumerate() {
+            match pixel {
+                Pixel::Cross => bits.set(i, false),
+                Pixel::Color(BLANK) => {}
+                Pixel::Color(c) if c.eq(&color) => bits.set(i, true),
+                Pixel::Color(_) => other_color = true,
+            }
+        }
+        let counts: Vec<u32> = clues.iter().map(|clue| clue.count).collect();
+        let solved = match solve_line(&bits, &counts) {
+            Ok(_) if other_color => Err(Reason::NoPlacement),
+            solved => solved,
+        }
+        .map_err(|reason| Contradiction::new(game_line, reason))?;
+
+        let mut changes = false;
+        let proposition = line
+            .iter()
+            .enumerate()
+            .map(|(i, pixel)| {
+                if **pixel != Pixel::Color(BLANK) {
+                    return None;
+                }
+                let pixel = match solved.get(i)? {
+                    true => Pixel::Color(color),
+                    false => Pixel::Cross,
+                };
+                changes = true;
+                Some(pixel)
+            })
+            .collect();
+        if changes {
+            Ok(Some(Proposition::new(proposition, game_line)))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::Game;
+    use crate::picross_image::Clue;
+    use crate::solver::{GameView, Solver};
+    use crate::text;
+
+    use super::*;
+
+    const BLACK: u8 = 1;
+    const UNKNOWN: Pixel = Pixel::Color(BLANK);
+
+    fn propose(line: &[Pixel], clues: &[u32]) -> Result<Option<Proposition>> {
+        let clues: Vec<Clue> = clues.iter().map(|&count| Clue { color: BLACK, count }).collect();
+        let game_line = GameLine {
+            view: GameView::Row,
+            board_line: line.iter().collect(),
+            clues: &clues,
+            index: 0,
+        };
+        Placements.get_proposition(&game_line)
+    }
+
+    #[test]
+    fn it_finds_overlapping_cells() {
+        let proposition = propose(&[UNKNOWN; 5], &[3]).unwrap().unwrap();
+        assert_eq!(
+            proposition.line,
+            vec![None, None, Some(Pixel::Color(BLACK)), None, None]
+        );
+
+        let line = [UNKNOWN, Pixel::Color(BLACK), UNKNOWN, UNKNOWN, UNKNOWN];
+        let proposition = propose(&line, &[2]).unwrap().unwrap();
+        assert_eq!(
+            proposition.line,
+            vec![None, None, None, Some(Pixel::Cross), Some(Pixel::Cross)]
+        );
+
+        assert!(propose(&[UNKNOWN; 5], &[1, 1]).unwrap().is_none());
+    }
+
+    #[test]
+    fn it_detects_contradictions() {
+        let reason = |line: &[Pixel], clues: &[u32]| propose(line, clues).err().unwrap().reason;
+        assert_eq!(reason(&[UNKNOWN; 4], &[2, 2]), Reason::TooLong);
+        assert_eq!(reason(&[Pixel::Cross; 3], &[1]), Reason::NoPlacement);
+
+        let line = [Pixel::Color(BLACK), UNKNOWN, UNKNOWN, Pixel::Color(BLACK)];
+        assert_eq!(reason(&line, &[2]), Reason::NoPlacement);
+
+        // A cell of a color that isn't in the clues
+        assert_eq!(reason(&[Pixel::Color(BLACK + 1), UNKNOWN], &[1]), Reason::NoPlacement);
+    }
+
+    #[test]
+    fn it_solves_the_game() {
+        let mut game = Game::new("test/4x4-c.png").unwrap();
+        let solver = Solver {
+            algos: vec![Box::new(Placements {})],
+            domains: false,
+            deduced: Default::default(),
+        };
+        while let Some(proposition) = solver.solve(&game).unwrap() {
+            proposition.merge(&mut game.board);
+        }
+        assert!(game.is_finished());
+    }
+
+    #[test]
+    fn it_completes_a_partial_board() {
+        let solution = ".###.\n#####\n.###.\n";
+        let mut game = Game::with_image(text::parse_image(solution).unwrap());
+        game.board = text::parse_board("?????\n?????\n.????\n", &game.image.palette).unwrap();
+        let solver = Solver {
+            algos: vec![Box::new(Placements {})],
+            domains: false,
+            deduced: Default::default(),
+        };
+
+        let proposition = solver.solve(&game).unwrap().unwrap();
+        proposition.merge(&mut game.board);
+        assert_eq!(text::board_to_text(&game.board, &game.image.palette).unwrap(), "??#??\n?????\n.????\n");
+
+        while let Some(proposition) = solver.solve(&game).unwrap() {
+            proposition.merge(&mut game.board);
+        }
+        assert_eq!(text::board_to_text(&game.board, &game.image.palette).unwrap(), solution);
+    }
+
+}
@@ -0,0 +1,276 @@
+//! Plain text grids, one character per cell :
+//!
+//! - `#` : a black cell
+//! - `.` : an empty cell (a cross on a board)
+//! - `?` : an unknown cell, only on a board
+//! - a letter : a colored cell, see [`DEFAULT_PALETTE`]
+//!
+//! Lines like `a = #ff8000` before the grid define the color of a letter.
+//! Empty lines are ignored.
+
+use image::{Rgb, RgbImage};
+
+use crate::board::{Board, Pixel};
+use crate::palette::{Palette, BLANK};
+use crate::parse_error::{ParseError, ParseErrorKind};
+use crate::picross_image::{Image, ImageError, WHITE};
+
+const BLACK: Rgb<u8> = Rgb([0, 0, 0]);
+
+/// The colors of the letters, unless redefined in the text
+pub const DEFAULT_PALETTE: [(char, Rgb<u8>); 8] = [
+    ('r', Rgb([0xFF, 0x00, 0x00])),
+    ('g', Rgb([0x00, 0x80, 0x00])),
+    ('b', Rgb([0x00, 0x00, 0xFF])),
+    ('y', Rgb([0xFF, 0xFF, 0x00])),
+    ('o', Rgb([0xFF, 0xA5, 0x00])),
+    ('p', Rgb([0x80, 0x00, 0x80])),
+    ('c', Rgb([0x00, 0xFF, 0xFF])),
+    ('m', Rgb([0xFF, 0x00, 0xFF])),
+];
+
+type Result<T> = std::result::Result<T, ParseError>;
+
+/// Write `#rrggbb`
+fn hex(color: &Rgb<u8>) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
+/// Parse `a = #rrggbb`
+fn parse_color_definition(line: &str) -> Option<(char, Rgb<u8>)> {
+    let (letter, color) = line.split_once('=')?;
+    let mut letter = letter.trim().chars();
+    let color = color.trim().strip_prefix('#')?;
+    match (letter.next(), letter.next()) {
+        (Some(letter), None) if letter.is_ascii_alphabetic() && color.len() == 6 => {
+            let channel = |i: usize| u8::from_str_radix(color.get(i..i + 2)?, 16).ok();
+            Some((letter, Rgb([channel(0)?, channel(2)?, channel(4)?])))
+        }
+        _ => None,
+    }
+}
+
+/// A cell of the grid
+#[derive(Clone, Copy, PartialEq)]
+enum Cell {
+    Unknown,
+    Empty,
+    Filled(Rgb<u8>),
+}
+
+/// A row of the grid, with its line number
+type Row = (usize, Vec<Cell>);
+
+/// Parse the text
+fn parse(text: &str) -> Result<Vec<Row>> {
+    let mut palette: Vec<(char, Rgb<u8>)> = DEFAULT_PALETTE.to_vec();
+    let mut rows: Vec<Row> = vec![];
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            continue;
+        }
+        if line.contains('=') {
+            if !rows.is_empty() {
+                let kind = ParseErrorKind::Syntax("Color definition after the grid".to_string());
+                return Err(ParseError::at(n + 1, 1, kind));
+            }
+            let definition = parse_color_definition(line).ok_or_else(|| {
+                ParseError::at(n + 1, 1, ParseErrorKind::InvalidColor(line.trim().to_string()))
+            })?;
+            palette.retain(|(letter, _)| *letter != definition.0);
+            palette.push(definition);
+            continue;
+        }
+        let mut row = vec![];
+        for (col, c) in line.chars().enumerate() {
+            let pixel = match c {
+                '#' => Cell::Filled(BLACK),
+                '.' => Cell::Empty,
+                '?' => Cell::Unknown,
+                c => match palette.iter().find(|(letter, _)| *letter == c) {
+                    Some((_, color)) => Cell::Filled(*color),
+                    None => {
+                        return Err(ParseError::at(n + 1, col + 1, ParseErrorKind::UnknownSymbol(c)))
+                    }
+                },
+            };
+            row.push(pixel);
+        }
+        if let Some((_, first)) = rows.first().filter(|(_, first)| first.len() != row.len()) {
+            let kind = ParseErrorKind::DimensionMismatch {
+                what: "cells",
+                expected: first.len(),
+                found: row.len(),
+            };
+            return Err(ParseError::at(n + 1, first.len().min(row.len()) + 1, kind));
+        }
+        rows.push((n + 1, row));
+    }
+    if rows.is_empty() || rows[0].1.is_empty() {
+        return Err(ParseError::new(ParseErrorKind::Empty));
+    }
+    Ok(rows)
+}
+
+/// Parse a solution, with no unknown cells
+pub fn parse_image(text: &str) -> Result<Image> {
+    let rows = parse(text)?;
+    let mut img = RgbImage::new(rows[0].1.len() as u32, rows.len() as u32);
+    for (y, (line, row)) in rows.iter().enumerate() {
+        for (x, pixel) in row.iter().enumerate() {
+            let color = match pixel {
+                Cell::Filled(color) => *color,
+                Cell::Empty => WHITE,
+                Cell::Unknown => return Err(ParseError::at(*line, x + 1, ParseErrorKind::UnknownCell)),
+            };
+            img.put_pixel(x as u32, y as u32, color);
+        }
+    }
+    Ok(Image::from_rgb_image(img))
+}
+
+/// Parse a partially solved board, its colors being in the palette of the puzzle
+pub fn parse_board(text: &str, palette: &Palette) -> Result<Board> {
+    let rows = parse(text)?;
+    let mut board = Board::new(rows[0].1.len(), rows.len());
+    for (y, (line, row)) in rows.iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            let pixel = match cell {
+                Cell::Unknown => continue,
+                Cell::Empty => Pixel::Cross,
+                Cell::Filled(color) => match palette.index(color) {
+                    Some(index) => Pixel::Color(index),
+                    None => {
+                        let kind = ParseErrorKind::UnknownColor(hex(color));
+                        return Err(ParseError::at(*line, x + 1, kind));
+                    }
+                },
+            };
+            board.set_pixel(x, y, &pixel);
+        }
+    }
+    Ok(board)
+}
+
+/// Write the grid, `cell` giving the cell at (x, y), after the definition of the colors
+/// that are not in the default palette.
+///
+/// Fails if there are more colors than letters.
+fn write<F>(width: usize, height: usize, cell: F) -> std::result::Result<String, ImageError>
+where
+    F: Fn(usize, usize) -> Cell,
+{
+    let mut palette: Vec<(char, Rgb<u8>)> = DEFAULT_PALETTE.to_vec();
+    let mut definitions = String::new();
+    let mut grid = String::new();
+    for y in 0..height {
+        for x in 0..width {
+            let c = match cell(x, y) {
+                Cell::Unknown => '?',
+                Cell::Empty => '.',
+                Cell::Filled(BLACK) => '#',
+                Cell::Filled(color) => match palette.iter().find(|(_, used)| *used == color) {
+                    Some((letter, _)) => *letter,
+                    None => {
+                        let letter = ('a'..='z')
+                            .chain('A'..='Z')
+                            .find(|l| !palette.iter().any(|(used, _)| used == l))
+                            .ok_or(ImageError::TooManyLettersError)?;
+                        definitions.push_str(&format!("{} = {}\n", letter, hex(&color)));
+                        palette.push((letter, color));
+                        letter
+                    }
+                },
+            };
+            grid.push(c);
+        }
+        grid.push('\n');
+    }
+    Ok(definitions + &grid)
+}
+
+/// Write the solution picture of a puzzle
+pub fn image_to_text(img: &RgbImage) -> std::result::Result<String, ImageError> {
+    write(img.width() as usize, img.height() as usize, |x, y| {
+        let color = *img.get_pixel(x as u32, y as u32);
+        if color == WHITE {
+            Cell::Empty
+        } else {
+            Cell::Filled(color)
+        }
+    })
+}
+
+/// Write the board, with `?` for the unknown cells
+pub fn board_to_text(board: &Board, palette: &Palette) -> std::result::Result<String, ImageError> {
+    write(board.width(), board.height(), |x, y| match board.get_pixel(x, y) {
+        Pixel::Color(BLANK) => Cell::Unknown,
+        Pixel::Color(color) => Cell::Filled(palette.color(*color)),
+        Pixel::Cross => Cell::Empty,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_a_solution() {
+        let image = parse_image(
+            "
+            ####
+            #...
+            #...
+            ####
+            "
+            .replace(' ', "")
+            .as_str(),
+        )
+        .unwrap();
+        let png = Image::from_image("test/4x4-c.png").unwrap();
+        assert_eq!(image.width, 4);
+        assert_eq!(image.height, 4);
+        assert_eq!(image.rows, png.rows);
+        assert_eq!(image.cols, png.cols);
+        assert_eq!(image_to_text(&image.to_rgb_image().unwrap()).unwrap(), "####\n#...\n#...\n####\n");
+    }
+
+    #[test]
+    fn it_parses_colors_and_boards() {
+        let text = "z = #123456\nrz?\n.#?\n";
+        let image = parse_image("z = #123456\nrz#\n").unwrap();
+        let palette = &image.palette;
+        let board = parse_board(text, palette).unwrap();
+        assert_eq!(*board.get_pixel(0, 0), Pixel::Color(1));
+        assert_eq!(*board.get_pixel(1, 0), Pixel::Color(2));
+        assert_eq!(*board.get_pixel(2, 0), Pixel::Color(BLANK));
+        assert_eq!(*board.get_pixel(0, 1), Pixel::Cross);
+        assert_eq!(palette.color(2), Rgb([0x12, 0x34, 0x56]));
+        assert_eq!(board_to_text(&board, palette).unwrap(), "a = #123456\nra?\n.#?\n");
+
+        let error = |line, column, kind| Some(ParseError::at(line, column, kind));
+        assert_eq!(parse_image(text).err(), error(2, 3, ParseErrorKind::UnknownCell));
+        let ragged = ParseErrorKind::DimensionMismatch { what: "cells", expected: 2, found: 1 };
+        assert_eq!(parse_board("#.\n#", palette).err(), error(2, 2, ragged));
+        assert_eq!(parse_board("#.\n#z", palette).err(), error(2, 2, ParseErrorKind::UnknownSymbol('z')));
+        let green = ParseErrorKind::UnknownColor("#008000".to_string());
+        assert_eq!(parse_board("#.\n#g", palette).err(), error(2, 2, green));
+        let red = ParseErrorKind::InvalidColor("a = red".to_string());
+        assert_eq!(parse_board("a = red\n#", palette).err(), error(1, 1, red));
+        let late = ParseErrorKind::Syntax("Color definition after the grid".to_string());
+        assert_eq!(parse_image("#.\n\nz = #123456\n").err(), error(3, 1, late));
+        assert_eq!(parse_board("\n\n", palette).err(), Some(ParseError::new(ParseErrorKind::Empty)));
+    }
+
+    #[test]
+    fn it_rejects_pictures_with_more_colors_than_letters() {
+        // 44 letters are left by the default colors
+        let img = RgbImage::from_fn(44, 1, |x, _| Rgb([x as u8 + 1, 0x40, 0]));
+        let text = image_to_text(&img).unwrap();
+        assert_eq!(parse_image(&text).unwrap().palette.len(), 45);
+
+        let img = RgbImage::from_fn(45, 1, |x, _| Rgb([x as u8 + 1, 0x40, 0]));
+        assert!(matches!(image_to_text(&img), Err(ImageError::TooManyLettersError)));
+    }
+}
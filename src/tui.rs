@@ -0,0 +1,130 @@
+use std::cell::Cell;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::rc::Rc;
+
+use picross_rs::{render, Event, Game, GameView, Mode};
+
+const HELP: &str = "\
+commands :
+  f <x> <y> [n]      fill the cell, with the color n of the list below, 0 by default
+  x <x> <y>          cross the cell
+  c <x> <y>          clear the cell
+  m                  show the rows and columns with a mistake
+  u                  undo the last change
+  a                  turn the auto-cross of the completed lines on or off
+  ?                  give a hint for the next move
+  h                  show this help
+  q                  quit";
+
+/// Play the game in the terminal, reading the commands from stdin.
+///
+/// Returns true if the puzzle was solved.
+pub fn play(game: &mut Game) -> io::Result<bool> {
+    let colors = game.image.colors();
+    let (width, height) = (game.board.width(), game.board.height());
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+
+    let over = Rc::new(Cell::new(false));
+    let game_over = Rc::clone(&over);
+    game.subscribe(move |event| match event {
+        Event::Mistake { x, y, count } => println!("Mistake {} : ({}, {}) is wrong", count, x, y),
+        Event::Lost => {
+            println!("GAME OVER");
+            game_over.set(true);
+        }
+        Event::Solved => {
+            println!("YOU WIN");
+            game_over.set(true);
+        }
+        _ => {}
+    });
+
+    print_help(game);
+    let mut highlight = None;
+    loop {
+        print_board(game, highlight.take());
+        if let Mode::Penalty { max_mistakes, .. } = game.mode() {
+            let time = game.elapsed().as_secs();
+            let mistakes = game.mistake_count();
+            println!("Mistakes : {}/{}, time : {}:{:02}", mistakes, max_mistakes, time / 60, time % 60);
+        }
+        if over.get() {
+            return Ok(game.is_finished());
+        }
+        print!("> ");
+        io::stdout().flush()?;
+
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => return Ok(false),
+        };
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let args: Vec<usize> = words.iter().skip(1).filter_map(|w| w.parse().ok()).collect();
+        let in_board = |x: usize, y: usize| x < width && y < height;
+        match (words.first().copied(), args.as_slice()) {
+            (Some("q"), _) => return Ok(false),
+            (Some("m"), []) => show_mistakes(game),
+            (Some("u"), []) => {
+                if !game.undo() {
+                    println!("Nothing to undo");
+                }
+            }
+            (Some("?"), []) => match game.hint() {
+                Some(hint) => {
+                    println!("Hint : {}", hint);
+                    highlight = Some(hint.line());
+                }
+                None => println!("No hint found"),
+            },
+            (Some("a"), []) => {
+                game.assists.auto_cross = !game.assists.auto_cross;
+                println!("Auto-cross {}", if game.assists.auto_cross { "on" } else { "off" });
+            }
+            (Some("f"), &[x, y]) if in_board(x, y) && !colors.is_empty() => game.fill(x, y, colors[0]),
+            (Some("f"), &[x, y, n]) if in_board(x, y) && n < colors.len() => game.fill(x, y, colors[n]),
+            (Some("x"), &[x, y]) if in_board(x, y) => game.cross(x, y),
+            (Some("c"), &[x, y]) if in_board(x, y) => game.clear(x, y),
+            _ => print_help(game),
+        }
+    }
+}
+
+fn show_mistakes(game: &Game) {
+    let mistakes = game.mistakes();
+    if mistakes.is_empty() {
+        println!("No mistake found");
+    }
+    for mistake in mistakes {
+        println!("Mistake on {} {}", mistake.view, mistake.index);
+    }
+}
+
+/// Print the commands, and the colors of the puzzle with their number for `f`
+fn print_help(game: &Game) {
+    println!("{}", HELP);
+    let ansi = io::stdout().is_terminal();
+    let palette = &game.image.palette;
+    let colors: Vec<String> = game
+        .image
+        .colors()
+        .iter()
+        .enumerate()
+        .map(|(n, &color)| {
+            let rgb = palette.color(color);
+            let swatch = if ansi {
+                format!("\x1b[48;2;{};{};{}m  \x1b[0m ", rgb[0], rgb[1], rgb[2])
+            } else {
+                String::new()
+            };
+            format!("{} {}{}", n, swatch, palette.name(color))
+        })
+        .collect();
+    println!("colors :\n  {}", colors.join("\n  "));
+}
+
+/// Print the board with its clues, in colors if stdout is a terminal
+fn print_board(game: &Game, highlight: Option<(GameView, usize)>) {
+    let ansi = io::stdout().is_terminal();
+    print!("{}", render::to_terminal_highlighted(&game.image, &game.board, highlight, ansi));
+}
@@ -0,0 +1,290 @@
+use std::collections::HashMap;
+
+use image::{Rgb, RgbImage};
+
+use crate::board::Pixel;
+use crate::game::Game;
+use crate::palette::BLANK;
+use crate::picross_image::{Image, WHITE};
+use crate::solver::{deduce, Solver, SolverBuilder};
+
+const BLACK: Rgb<u8> = Rgb([0, 0, 0]);
+
+/// Number of sets of flips tried by [`suggest_flips`] to beat the greedy search
+const SEARCH_LIMIT: usize = 20_000;
+
+/// A pixel of the picture to change so the puzzle gets easier to solve
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Flip {
+    pub x: u32,
+    pub y: u32,
+    pub from: Rgb<u8>,
+    pub to: Rgb<u8>,
+}
+
+/// Solve the puzzle with the line solvers, starting from an empty board
+fn solve(image: Image, solver: &Solver) -> Game {
+    let mut game = Game::with_image(image);
+    // The clues come from a picture, so they can't contradict each other
+    let _ = solver.run(&mut game, |_, _, _| {});
+    game
+}
+
+fn unknown_cells(game: &Game) -> Vec<(u32, u32)> {
+    let mut cells = vec![];
+    for y in 0..game.board.height() {
+        for x in 0..game.board.width() {
+            if *game.board.get_pixel(x, y) == Pixel::Color(BLANK) {
+                cells.push((x as u32, y as u32));
+            }
+        }
+    }
+    cells
+}
+
+/// Get the cells that can't be deduced from the clues, line by line.
+///
+/// An empty result means the puzzle is line solvable, and so it has a unique solution.
+/// The other puzzles may still have a unique solution, see [`count_solutions`].
+pub fn ambiguous_cells(image: &Image) -> Vec<(u32, u32)> {
+    let solver = SolverBuilder::new().build();
+    unknown_cells(&solve(image.clone(), &solver))
+}
+
+/// Whether every cell can be deduced line by line, like [`ambiguous_cells`] being empty
+fn is_line_solvable(image: Image) -> bool {
+    deduce(&Game::with_image(image)).is_ok_and(|board| board.count_unknown() == 0)
+}
+
+/// Count the solutions of the puzzle from its clues, stopping at `limit`.
+///
+/// The cells which can't be deduced line by line are guessed one at a time, so this also
+/// counts the solutions of the puzzles that aren't line solvable.
+pub fn count_solutions(image: &Image, limit: usize) -> usize {
+    let puzzle = Image::from_clues(image.rows.clone(), image.cols.clone(), image.palette.clone());
+    let mut guesses: Vec<Pixel> = puzzle.colors().into_iter().map(Pixel::Color).collect();
+    guesses.push(Pixel::Cross);
+    count(&mut Game::with_image(puzzle), &guesses, limit)
+}
+
+/// Count the solutions from the board of the game, guessing its first unknown cell
+fn count(game: &mut Game, guesses: &[Pixel], limit: usize) -> usize {
+    let board = match deduce(game) {
+        Ok(board) => board,
+        Err(_) => return 0,
+    };
+    let unknown = (0..board.height())
+        .flat_map(|y| (0..board.width()).map(move |x| (x, y)))
+        .find(|&(x, y)| *board.get_pixel(x, y) == Pixel::Color(BLANK));
+    let (x, y) = match unknown {
+        Some(cell) => cell,
+        None => return usize::from(board.eq(&game.image)),
+    };
+    let mut solutions = 0;
+    for guess in guesses {
+        if solutions >= limit {
+            break;
+        }
+        game.board = board.clone();
+        game.board.set_pixel(x, y, guess);
+        solutions += count(game, guesses, limit - solutions);
+    }
+    solutions
+}
+
+/// Whether the puzzle has exactly one solution, line solvable or not
+pub fn has_unique_solution(image: &Image) -> bool {
+    count_solutions(image, 2) == 1
+}
+
+/// The color used to fill a white pixel : the most used one in the picture
+fn fill_color(img: &RgbImage) -> Rgb<u8> {
+    let mut counts = HashMap::new();
+    for pixel in img.pixels() {
+        if *pixel != WHITE {
+            *counts.entry(pixel.0).or_insert(0) += 1;
+        }
+    }
+    counts
+        .into_iter()
+        .max_by_key(|&(color, count)| (count, color))
+        .map_or(BLACK, |(color, _)| Rgb(color))
+}
+
+/// Change the given pixels of the picture, and compute the new clues. The palette of the
+/// puzzle is kept, with its names.
+///
+/// A puzzle without a solution picture is returned unchanged.
+pub fn apply_flips(image: &Image, flips: &[Flip]) -> Image {
+    let mut img = match image.to_rgb_image() {
+        Some(img) => img,
+        None => return image.clone(),
+    };
+    for flip in flips {
+        img.put_pixel(flip.x, flip.y, flip.to);
+    }
+    Image::with_palette(img, image.palette.clone())
+}
+
+/// The flip of the pixel of the solution, from white to `color` or from its color to white
+fn flip(image: &Image, x: u32, y: u32, color: Rgb<u8>) -> Option<Flip> {
+    let from = image.palette.color(image.solution(x, y)?);
+    let to = if from == WHITE { color } else { WHITE };
+    Some(Flip { x, y, from, to })
+}
+
+/// Flip, at each step, the ambiguous pixel which clears the most ambiguous cells, until
+/// the puzzle is line solvable. Returns `None` if no flip clears an ambiguous cell.
+fn greedy_flips(image: &Image) -> Option<Vec<Flip>> {
+    let solver = SolverBuilder::new().build();
+    let color = fill_color(&image.to_rgb_image()?);
+
+    let mut flips: Vec<Flip> = vec![];
+    let mut ambiguous = ambiguous_cells(image);
+    while !ambiguous.is_empty() {
+        let current = apply_flips(image, &flips);
+        let mut best: Option<(Flip, usize)> = None;
+        for &(x, y) in &ambiguous {
+            if flips.iter().any(|flip| flip.x == x && flip.y == y) {
+                continue;
+            }
+            let flip = flip(&current, x, y, color)?;
+            let remaining = unknown_cells(&solve(apply_flips(&current, &[flip]), &solver)).len();
+            if best.is_none_or(|(_, n)| remaining < n) {
+                best = Some((flip, remaining));
+            }
+        }
+        match best {
+            Some((flip, remaining)) if remaining < ambiguous.len() => flips.push(flip),
+            _ => return None,
+        }
+        ambiguous = unknown_cells(&solve(apply_flips(image, &flips), &solver));
+    }
+    Some(flips)
+}
+
+/// A set of `size` candidates whose flips make the puzzle line solvable, the sets being
+/// tried in order. `budget` is the number of sets which can still be tried.
+fn find_flips(image: &Image, candidates: &[Flip], size: usize, budget: &mut usize) -> Option<Vec<Flip>> {
+    let n = candidates.len();
+    if size > n {
+        return None;
+    }
+    let mut indices: Vec<usize> = (0..size).collect();
+    while *budget > 0 {
+        *budget -= 1;
+        let flips: Vec<Flip> = indices.iter().map(|&i| candidates[i]).collect();
+        if is_line_solvable(apply_flips(image, &flips)) {
+            return Some(flips);
+        }
+        // The next set : increase the last index which can be, and reset the following ones
+        let i = (0..size).rev().find(|&i| indices[i] < n - size + i)?;
+        indices[i] += 1;
+        for j in i + 1..size {
+            indices[j] = indices[j - 1] + 1;
+        }
+    }
+    None
+}
+
+/// Look for the fewest pixels to flip so the puzzle gets line solvable, keeping the
+/// picture as close as possible to the original one.
+///
+/// A greedy search first flips, at each step, the ambiguous pixel which clears the most
+/// ambiguous cells. Then the smaller sets of ambiguous pixels of the picture are tried,
+/// from the smallest, so no fewer flips of these pixels make the puzzle line solvable.
+/// The sets grow fast with the number of ambiguous pixels : after 20 000 sets, the
+/// greedy flips are kept even if fewer flips could be found.
+///
+/// Returns `None` if the greedy search finds no flip clearing an ambiguous cell, or if the
+/// puzzle has no picture.
+pub fn suggest_flips(image: &Image) -> Option<Vec<Flip>> {
+    let greedy = greedy_flips(image)?;
+    let color = fill_color(&image.to_rgb_image()?);
+    let candidates: Vec<Flip> = ambiguous_cells(image)
+        .into_iter()
+        .filter_map(|(x, y)| flip(image, x, y, color))
+        .collect();
+    let mut budget = SEARCH_LIMIT;
+    for size in 1..greedy.len() {
+        if let Some(flips) = find_flips(image, &candidates, size, &mut budget) {
+            return Some(flips);
+        }
+    }
+    Some(greedy)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::palette::Palette;
+    use crate::text;
+
+    use super::*;
+
+    #[test]
+    fn it_fixes_ambiguous_pictures() {
+        let image = Image::from_image("test/4x4-c.png").unwrap();
+        assert!(ambiguous_cells(&image).is_empty());
+        assert_eq!(suggest_flips(&image), Some(vec![]));
+
+        // A diagonal can be mirrored
+        let mut img = RgbImage::from_pixel(2, 2, WHITE);
+        img.put_pixel(0, 0, BLACK);
+        img.put_pixel(1, 1, BLACK);
+        let image = Image::from_rgb_image(img);
+        assert_eq!(ambiguous_cells(&image).len(), 4);
+
+        let flips = suggest_flips(&image).unwrap();
+        assert_eq!(flips.len(), 1);
+        assert!(ambiguous_cells(&apply_flips(&image, &flips)).is_empty());
+    }
+
+    #[test]
+    fn it_counts_the_solutions() {
+        let mut img = RgbImage::from_pixel(2, 2, WHITE);
+        img.put_pixel(0, 0, BLACK);
+        img.put_pixel(1, 1, BLACK);
+        let image = Image::from_rgb_image(img);
+        assert_eq!(count_solutions(&image, 10), 2);
+        assert_eq!(count_solutions(&image, 1), 1);
+        assert!(!has_unique_solution(&image));
+
+        // Unique, but not line solvable
+        let image = text::parse_image("...##\n..##.\n..#..\n...##\n##.#.\n").unwrap();
+        assert!(!ambiguous_cells(&image).is_empty());
+        assert!(has_unique_solution(&image));
+        assert!(has_unique_solution(&Image::from_image("test/4x4-c.png").unwrap()));
+    }
+
+    #[test]
+    fn it_suggests_the_fewest_flips() {
+        let image = text::parse_image(".#.##\n#....\n..#..\n.#.#.\n#....\n").unwrap();
+        assert_eq!(greedy_flips(&image).unwrap().len(), 3);
+
+        let flips = suggest_flips(&image).unwrap();
+        assert_eq!(flips.len(), 2);
+        assert!(ambiguous_cells(&apply_flips(&image, &flips)).is_empty());
+
+        // No single flip is enough
+        let color = fill_color(&image.to_rgb_image().unwrap());
+        for (x, y) in ambiguous_cells(&image) {
+            let flip = flip(&image, x, y, color).unwrap();
+            assert!(!ambiguous_cells(&apply_flips(&image, &[flip])).is_empty());
+        }
+    }
+
+    #[test]
+    fn it_keeps_the_palette_of_the_puzzle() {
+        let mut palette = Palette::new();
+        let ink = palette.add_named(Rgb([20, 20, 60]), "ink").unwrap();
+        let mut img = RgbImage::from_pixel(2, 2, WHITE);
+        img.put_pixel(0, 0, palette.color(ink));
+        img.put_pixel(1, 1, palette.color(ink));
+        let image = Image::with_palette(img, palette.clone());
+
+        let flips = suggest_flips(&image).unwrap();
+        let fixed = apply_flips(&image, &flips);
+        assert_eq!(fixed.palette, palette);
+        assert_eq!(fixed.palette.name(ink), "ink");
+    }
+}
@@ -1,26 +1,40 @@
+use std::error::Error;
+
 use glutin_window::GlutinWindow as Window;
 
 use opengl_graphics::{GlGraphics, OpenGL};
 
-use piston::{MouseCursorEvent, ButtonEvent};
+use piston::MouseCursorEvent;
 use piston::event_loop::{EventSettings, Events};
 use piston::window::WindowSettings;
 use piston::input::*;
 
-use picross_rs::game::Game;
+use picross_rs::{render, Clue, Event, Game, GameView, Pixel, BLANK};
+
+/// Show the game in a window, and give it back when the window is closed
+pub fn show(mut game: Game) -> Result<Game, Box<dyn Error>> {
+    game.subscribe(|event| match event {
+        Event::Mistake { count, .. } => println!("Mistake {}", count),
+        Event::Lost => println!("GAME OVER"),
+        Event::Solved => println!("YOU WIN"),
+        _ => {}
+    });
 
-pub fn show(game: Game) {
     let opengl = OpenGL::V3_2;
     let mut window: Window = WindowSettings::new("Picross - Rust", [200, 200])
         .graphics_api(opengl)
         .exit_on_esc(true)
-        .build()
-        .unwrap();
+        .build()?;
 
     // Create a new game and run it.
+    let color = game.image.colors().first().copied().unwrap_or(BLANK);
     let mut app = App {
         gl: GlGraphics::new(opengl),
         mouse_coords: Vec2f{ x: 0.0, y: 0.0 },
+        window_size: Vec2f{ x: 200.0, y: 200.0 },
+        show_mistakes: false,
+        hint: None,
+        color,
         game
     };
 
@@ -31,23 +45,15 @@ pub fn show(game: Game) {
             app.render(&args);
         }
 
-        if let Some(_args) = e.update_args() {
-            println!("update_args()")
-            // app.update(&args);
-        }
-
         if let Some(args) = e.mouse_cursor_args() {
             app.on_mouse_move(&args);
         }
 
-        if let Some(a) = e.button_args() {
-            println!("mouse_cursor_args {:?}", a);
-        }
-
         if let Some(button) = e.press_args() {
             app.on_button_press(&button);
         }
     }
+    Ok(app.game)
 }
 
 struct Vec2f {
@@ -57,11 +63,33 @@ struct Vec2f {
 pub struct App {
     gl: GlGraphics, // OpenGL drawing backend.
     mouse_coords: Vec2f,
+    window_size: Vec2f,
+    /// Highlight the rows and columns with a mistake, toggled with the M key
+    show_mistakes: bool,
+    /// The line of the last hint, asked with the H key
+    hint: Option<(GameView, usize)>,
+    /// The palette index filled by the left click, picked with the number keys or the C
+    /// key, and shown in the top left corner
+    color: u8,
     game: Game
 }
 
 const BG_COLOR: [f32; 4] = [0.9, 0.9, 0.9, 1.0];
 const LINE_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+const CROSS_COLOR: [f32; 4] = [0.75, 0.25, 0.25, 1.0];
+const MISTAKE_COLOR: [f32; 4] = [1.0, 0.0, 0.0, 0.25];
+const HINT_COLOR: [f32; 4] = [1.0, 0.55, 0.0, 0.3];
+const SATISFIED_COLOR: [f32; 4] = [0.6, 0.6, 0.6, 1.0];
+
+fn to_color(color: &image::Rgb<u8>) -> [f32; 4] {
+    [color[0] as f32 / 255.0, color[1] as f32 / 255.0, color[2] as f32 / 255.0, 1.0]
+}
+
+/// Number of cells on the left and on the top of the board, where the clues are drawn
+fn margins(game: &Game) -> (usize, usize) {
+    let max_len = |clues: &Vec<Vec<Clue>>| clues.iter().map(Vec::len).max().unwrap_or(0).max(1);
+    (max_len(&game.image.rows), max_len(&game.image.cols))
+}
 
 impl App {
     fn render(&mut self, args: &RenderArgs) {
@@ -69,6 +97,14 @@ impl App {
 
         let width = args.window_size[0];
         let height = args.window_size[1];
+        self.window_size.x = width;
+        self.window_size.y = height;
+        let game = &self.game;
+        let board = &game.board;
+        let mistakes = if self.show_mistakes { game.mistakes() } else { vec![] };
+        let hint = self.hint;
+        let selected = to_color(&game.image.palette.color(self.color));
+        let (left, top) = margins(game);
 
         self.gl.draw(args.viewport(), |c, gl| {
             // Clear the screen.
@@ -76,17 +112,72 @@ impl App {
 
             let transform = c.transform;
 
+            let w = width / (board.width() + left) as f64;
+            let h = height / (board.height() + top) as f64;
+            let x0 = left as f64 * w;
+            let y0 = top as f64 * h;
+
+            // The selected color, in the corner between the clues
+            rectangle(selected, [0.0, 0.0, w, h], transform, gl);
+
+            // The clues, from the board to the edges of the window
+            let mut draw_clues = |view: GameView, index: usize, clues: &Vec<Clue>| {
+                let satisfied = game.satisfied_clues(view, index);
+                for (n, (clue, done)) in clues.iter().zip(satisfied).rev().enumerate() {
+                    let (cx, cy) = match view {
+                        GameView::Row => (x0 - (n + 1) as f64 * w, y0 + index as f64 * h),
+                        GameView::Column => (x0 + index as f64 * w, y0 - (n + 1) as f64 * h),
+                    };
+                    let color = if done { SATISFIED_COLOR } else { to_color(&game.image.palette.color(clue.color)) };
+                    for [sx, sy, sw, sh] in render::number_squares(clue.count) {
+                        rectangle(color, [cx + sx * w, cy + sy * h, sw * w, sh * h], transform, gl);
+                    }
+                }
+            };
+            for (y, clues) in game.image.rows.iter().enumerate() {
+                draw_clues(GameView::Row, y, clues);
+            }
+            for (x, clues) in game.image.cols.iter().enumerate() {
+                draw_clues(GameView::Column, x, clues);
+            }
+
+            for y in 0..board.height() {
+                for x in 0..board.width() {
+                    let cell = [x0 + x as f64 * w, y0 + y as f64 * h, w, h];
+                    match board.get_pixel(x, y) {
+                        Pixel::Color(color) => {
+                            rectangle(to_color(&game.image.palette.color(*color)), cell, transform, gl);
+                        }
+                        Pixel::Cross => {
+                            let cross = line::Line::new(CROSS_COLOR, 1.0);
+                            cross.draw([cell[0], cell[1], cell[0] + w, cell[1] + h], &Default::default(), transform, gl);
+                            cross.draw([cell[0] + w, cell[1], cell[0], cell[1] + h], &Default::default(), transform, gl);
+                        }
+                    }
+                }
+            }
+
+            let highlights = mistakes
+                .iter()
+                .map(|mistake| (MISTAKE_COLOR, (mistake.view, mistake.index)))
+                .chain(hint.map(|line| (HINT_COLOR, line)));
+            for (color, (view, index)) in highlights {
+                let area = match view {
+                    GameView::Row => [x0, y0 + index as f64 * h, width - x0, h],
+                    GameView::Column => [x0 + index as f64 * w, y0, w, height - y0],
+                };
+                rectangle(color, area, transform, gl);
+            }
+
             let line = line::Line::new(LINE_COLOR, 1.0);
-            let w = width / self.game.board.width() as f64;
-            for n_col in 1..self.game.board.width() {
-                let x = n_col as f64 * w;
-                line.draw([x, 0.0, x, height], &Default::default(), transform, gl)
+            for n_col in 0..board.width() {
+                let x = x0 + n_col as f64 * w;
+                line.draw([x, y0, x, height], &Default::default(), transform, gl)
             }
 
-            let h = height / self.game.board.height() as f64;
-            for n_row in 1..self.game.board.height() {
-                let y = n_row as f64 * h;
-                line.draw([0.0, y, width, y], &Default::default(), transform, gl)
+            for n_row in 0..board.height() {
+                let y = y0 + n_row as f64 * h;
+                line.draw([x0, y, width, y], &Default::default(), transform, gl)
             }
 
             Rectangle::new_border(LINE_COLOR, 0.0)
@@ -95,10 +186,34 @@ impl App {
     }
 
     fn on_mouse_click(&mut self, button: &MouseButton) {
-        if let &MouseButton::Left = button {
-            // self.selected_cell = Some(field::Coords{
-            //     x: (self.mouse_coords.x / self.settings.cell_size.x) as u8,
-            //     y: (self.mouse_coords.y / self.settings.cell_size.y) as u8 });
+        let (left, top) = margins(&self.game);
+        let board = &self.game.board;
+        let x = self.mouse_coords.x * (board.width() + left) as f64 / self.window_size.x;
+        let y = self.mouse_coords.y * (board.height() + top) as f64 / self.window_size.y;
+        if x < left as f64 || y < top as f64 {
+            return;
+        }
+        let x = x as usize - left;
+        let y = y as usize - top;
+        if x >= board.width() || y >= board.height() {
+            return;
+        }
+        let pixel = match button {
+            MouseButton::Left if self.color != BLANK => Pixel::Color(self.color),
+            MouseButton::Right => Pixel::Cross,
+            _ => return
+        };
+        // Clicking twice clears the cell
+        let pixel = if *board.get_pixel(x, y) == pixel { Pixel::Color(BLANK) } else { pixel };
+        self.game.play(x, y, pixel);
+        self.hint = None;
+    }
+
+    /// Select the color of the clues at this index, from 0
+    fn select_color(&mut self, index: usize) {
+        if let Some(&color) = self.game.image.colors().get(index) {
+            self.color = color;
+            println!("Color {} : {}", index + 1, self.game.image.palette.name(color));
         }
     }
 
@@ -108,11 +223,37 @@ impl App {
     }
 
     pub fn on_button_press(&mut self, button: &Button) {
-        match button {
-            &Button::Keyboard(_key) => {
+        match *button {
+            Button::Keyboard(Key::M) => {
+                self.show_mistakes = !self.show_mistakes;
+            },
+            Button::Keyboard(Key::H) => {
+                let hint = self.game.hint();
+                match &hint {
+                    Some(hint) => println!("Hint : {}", hint),
+                    None => println!("No hint found"),
+                }
+                self.hint = hint.map(|hint| hint.line());
+            },
+            Button::Keyboard(Key::U) => {
+                self.game.undo();
+            },
+            Button::Keyboard(Key::A) => {
+                self.game.assists.auto_cross = !self.game.assists.auto_cross;
+            },
+            Button::Keyboard(Key::C) => {
+                let colors = self.game.image.colors();
+                if let Some(index) = colors.iter().position(|&color| color == self.color) {
+                    self.select_color((index + 1) % colors.len());
+                }
+            },
+            Button::Keyboard(key) if (Key::D1..=Key::D9).contains(&key) => {
+                self.select_color(key as usize - Key::D1 as usize);
+            },
+            Button::Keyboard(_key) => {
                 // self.on_key_down(&key);
             },
-            &Button::Mouse(button) => {
+            Button::Mouse(button) => {
                 self.on_mouse_click(&button);
             }
             _ => {}